@@ -1,24 +1,26 @@
-use aoc2019::{intcode::Intcode, lines};
+use aoc2019::intcode::symbolic::{End, Expr};
+use aoc2019::intcode::Intcode;
+use aoc2019::lines;
 use std::error::Error;
 
 fn star1() -> Result<isize, Box<dyn Error>> {
     let mut ic = lines("input")?.next().unwrap().parse::<Intcode>()?;
     ic.set(12, 2);
-    ic.run()
+    Ok(ic.run()?)
 }
 
 fn star2() -> Result<isize, Box<dyn Error>> {
-    for verb in 0..100 {
-        for noun in 0..100 {
-            let mut ic = lines("input")?.next().unwrap().parse::<Intcode>()?;
-            ic.set(noun, verb);
-            let res = ic.run()?;
-            if res == 19_690_720 {
-                return Ok(100 * noun + verb);
-            }
-        }
-    }
-    Ok(0)
+    let ic = lines("input")?.next().unwrap().parse::<Intcode>()?;
+    let mut sym = ic.symbolic();
+    let noun = sym.symbol(1, 0..=99);
+    let verb = sym.symbol(2, 0..=99);
+    let model = sym
+        .find(|path| match path.end {
+            End::Halt => Some(Expr::equal(path.peek(0), Expr::constant(19_690_720))),
+            _ => None,
+        })
+        .ok_or("no noun and verb give 19690720")?;
+    Ok(100 * model[noun] + model[verb])
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
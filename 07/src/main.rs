@@ -1,63 +1,41 @@
+use aoc2019::intcode::sched::Outcome;
+use aoc2019::intcode::topology::Topology;
 use aoc2019::intcode::Intcode;
-use aoc2019::intcode::State;
 use aoc2019::lines;
 use std::error::Error;
+use std::ops::RangeInclusive;
 use std::str::FromStr;
 
 use itertools::Itertools;
 
-fn star1() -> Result<isize, Box<dyn Error>> {
-    let code = lines("input")?.next().unwrap();
-
-    let mut max = 0;
+const CHAIN: &str = "
+    node a $0, 0
+    node b $1
+    node c $2
+    node d $3
+    node e $4
+    chain a b c d e
+";
 
-    for phases in (0..=4).permutations(5) {
-        let mut input = 0;
-        for phase in phases {
-            let mut amp = Intcode::from_str(&code)?;
-            input = *amp.run_input(&[phase, input]).out().last().unwrap();
-        }
+fn max_thrust(topology: &str, phases: RangeInclusive<isize>) -> Result<isize, Box<dyn Error>> {
+    let code = Intcode::from_str(&lines("input")?.next().unwrap())?;
+    let topology = Topology::from_str(topology)?;
 
-        if input > max {
-            max = input;
-        }
+    let mut max = 0;
+    for phases in phases.permutations(topology.params()) {
+        let report = topology.run(&code, &phases)?;
+        assert_eq!(Outcome::Halted, report.outcome);
+        max = std::cmp::max(max, *report.output("e").last().unwrap());
     }
-
     Ok(max)
 }
 
-fn star2() -> Result<isize, Box<dyn Error>> {
-    let code = lines("input")?.next().unwrap();
-
-    let mut max = 0;
-    for phases in (5..=9).permutations(5) {
-        let mut amps = (0..5)
-            .map(|_| Intcode::from_str(&code))
-            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
-
-        for (phase, amp) in phases.into_iter().zip(&mut amps) {
-            assert_eq!(State::Wait(vec![]), amp.run_input(&[phase]));
-        }
-
-        let mut input = vec![0];
-        let mut halted = 0;
-        while halted == 0 {
-            for amp in &mut amps {
-                match amp.run_input(&input) {
-                    State::Wait(o) => {
-                        input = o;
-                    }
-                    State::Halt(o, _) => {
-                        input = o;
-                        halted += 1;
-                    }
-                }
-            }
-        }
+fn star1() -> Result<isize, Box<dyn Error>> {
+    max_thrust(CHAIN, 0..=4)
+}
 
-        max = std::cmp::max(max, input.pop().unwrap())
-    }
-    Ok(max)
+fn star2() -> Result<isize, Box<dyn Error>> {
+    max_thrust(&format!("{}\n    e -> a", CHAIN), 5..=9)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -5,12 +5,12 @@ use std::str::FromStr;
 
 fn star1() -> Result<isize, Box<dyn Error>> {
     let prog = lines("input")?.next().unwrap();
-    let out = Intcode::from_str(&prog)?.run_input(&[1]).out()[0];
+    let out = Intcode::from_str(&prog)?.run_input(&[1])?.out()[0];
     Ok(out)
 }
 fn star2() -> Result<isize, Box<dyn Error>> {
     let prog = lines("input")?.next().unwrap();
-    let out = Intcode::from_str(&prog)?.run_input(&[2]).out()[0];
+    let out = Intcode::from_str(&prog)?.run_input(&[2])?.out()[0];
     Ok(out)
 }
 
@@ -29,7 +29,7 @@ mod test {
         assert_eq!(
             vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99],
             *Intcode::from_str("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99")?
-                .run_input(&[])
+                .run_input(&[])?
                 .out()
         );
         Ok(())
@@ -39,7 +39,7 @@ mod test {
         assert_eq!(
             1219070632396864,
             *Intcode::from_str("1102,34915192,34915192,7,4,7,99,0")?
-                .run_input(&[])
+                .run_input(&[])?
                 .out()
                 .last()
                 .unwrap()
@@ -51,7 +51,7 @@ mod test {
         assert_eq!(
             1125899906842624,
             *Intcode::from_str("104,1125899906842624,99")?
-                .run_input(&[])
+                .run_input(&[])?
                 .out()
                 .last()
                 .unwrap()
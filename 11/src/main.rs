@@ -1,7 +1,7 @@
 use aoc2019::intcode::Intcode;
-use aoc2019::intcode::State;
 use aoc2019::lines;
 use std::borrow::Borrow;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::error::Error;
 use std::ops::Add;
@@ -32,41 +32,29 @@ impl<T: Add<Output = T>> Add for Vector<T> {
     }
 }
 
-struct Robot {
-    pos: Vector<isize>,
-    dir: Vector<isize>,
-    ic: Intcode,
-}
-
 fn paint(code: &str, panel: isize) -> Result<HashMap<Vector<isize>, isize>, Box<dyn Error>> {
-    let mut hull: HashMap<Vector<isize>, isize> = HashMap::new();
-    let mut r = Robot {
-        pos: Vector::new(0, 0),
-        dir: Vector::new(0, -1),
-        ic: Intcode::from_str(code)?,
-    };
-    hull.insert(Vector::new(0, 0), panel);
+    let hull = RefCell::new(HashMap::new());
+    let pos = Cell::new(Vector::new(0, 0));
+    let mut dir = Vector::new(0, -1);
     let mut total_out = 0;
-    loop {
-        let res = r.ic.run_input(&[hull.get(&r.pos).cloned().unwrap_or(0)]);
-        for c in res.out() {
-            match total_out % 2 {
-                0 => {
-                    hull.insert(r.pos, *c);
-                }
-                1 => {
-                    r.dir = r.dir.turn(*c);
-                    r.pos = r.pos + r.dir;
-                }
-                _ => unreachable! {},
+    hull.borrow_mut().insert(pos.get(), panel);
+
+    let mut camera = || Some(hull.borrow().get(&pos.get()).cloned().unwrap_or(0));
+    let mut robot = |c| {
+        match total_out % 2 {
+            0 => {
+                hull.borrow_mut().insert(pos.get(), c);
             }
-            total_out += 1;
-        }
-        if let State::Halt(_, _) = res {
-            break;
+            1 => {
+                dir = dir.turn(c);
+                pos.set(pos.get() + dir);
+            }
+            _ => unreachable! {},
         }
-    }
-    Ok(hull)
+        total_out += 1;
+    };
+    Intcode::from_str(code)?.run_with(&mut camera, &mut robot)?;
+    Ok(hull.into_inner())
 }
 
 fn star1() -> Result<usize, Box<dyn Error>> {
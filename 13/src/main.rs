@@ -5,7 +5,7 @@ use std::str::FromStr;
 fn star1() -> Result<usize, Box<dyn Error>> {
     let mut ic = intcode::Intcode::from_str(&lines("input")?.next().unwrap())?;
     Ok(ic
-        .run_input(&[])
+        .run_input(&[])?
         .out()
         .chunks(3)
         .filter(|t| t[2] == 2)
@@ -21,7 +21,7 @@ fn star2() -> Result<isize, Box<dyn Error>> {
     let mut input: &[isize] = &[];
     let mut out = intcode::State::Wait(vec![]);
     while !out.is_halt() {
-        out = ic.run_input(input);
+        out = ic.run_input(input)?;
         for xyt in out.out().chunks(3) {
             match (xyt[0], xyt[1], xyt[2]) {
                 (-1, 0, t) => {
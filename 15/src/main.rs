@@ -1,4 +1,4 @@
-use aoc2019::intcode::Intcode;
+use aoc2019::intcode::{Intcode, IntcodeError};
 use aoc2019::lines;
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -76,24 +76,25 @@ impl Robot {
             map,
         })
     }
-    fn peek(&mut self, dir: isize) -> isize {
-        let tile = self.mv(dir);
+    fn peek(&mut self, dir: isize) -> Result<isize, IntcodeError> {
+        let tile = self.mv(dir)?;
         if tile > 0 {
-            self.back(dir);
+            self.back(dir)?;
         }
-        tile
+        Ok(tile)
     }
-    fn back(&mut self, dir: isize) {
-        let tile = *self.ic.run_input(&[reverse(dir)]).out().first().unwrap();
+    fn back(&mut self, dir: isize) -> Result<(), IntcodeError> {
+        let tile = *self.ic.run_input(&[reverse(dir)])?.out().first().unwrap();
         assert!(tile > 0);
         self.path.pop();
+        Ok(())
     }
-    fn mv(&mut self, dir: isize) -> isize {
-        let tile = *self.ic.run_input(&[dir]).out().first().unwrap();
+    fn mv(&mut self, dir: isize) -> Result<isize, IntcodeError> {
+        let tile = *self.ic.run_input(&[dir])?.out().first().unwrap();
         if tile > 0 {
             self.path.push(self.pos().dir(dir));
         }
-        tile
+        Ok(tile)
     }
     fn find_path(&self, mut loc: Loc) -> Vec<isize> {
         let mut ret = Vec::new();
@@ -105,19 +106,21 @@ impl Robot {
         ret.reverse();
         ret
     }
-    fn follow_path(&mut self, path: &[isize]) {
+    fn follow_path(&mut self, path: &[isize]) -> Result<(), IntcodeError> {
         for dir in path {
-            self.mv(*dir);
+            self.mv(*dir)?;
         }
+        Ok(())
     }
-    fn follow_path_rev(&mut self, path: &[isize]) {
+    fn follow_path_rev(&mut self, path: &[isize]) -> Result<(), IntcodeError> {
         for dir in path.iter().rev() {
-            self.back(*dir);
+            self.back(*dir)?;
         }
+        Ok(())
     }
-    fn explore(&mut self, loc: Loc) -> Vec<Loc> {
+    fn explore(&mut self, loc: Loc) -> Result<Vec<Loc>, IntcodeError> {
         let path = self.find_path(loc);
-        self.follow_path(&path);
+        self.follow_path(&path)?;
         //println!("Robot Path: {:?}", self.path);
         //println!(" Want Path: {:?}", path);
 
@@ -128,7 +131,7 @@ impl Robot {
             if self.map.0.contains_key(&nloc) {
                 continue;
             }
-            let tile = self.peek(neigh);
+            let tile = self.peek(neigh)?;
             if tile == 0 {
                 continue;
             }
@@ -147,9 +150,9 @@ impl Robot {
         }
         //println!("Robot after explore: {:?}", self.pos());
         self.map.0.get_mut(&self.pos()).unwrap().neigh = neighs;
-        self.follow_path_rev(&path);
+        self.follow_path_rev(&path)?;
 
-        queue
+        Ok(queue)
     }
     fn dist(&self) -> usize {
         self.path.len()
@@ -187,7 +190,7 @@ fn star1() -> Result<usize, Box<dyn Error>> {
         if t.oxygen {
             return Ok(t.dist);
         }
-        queue.extend(r.explore(l));
+        queue.extend(r.explore(l)?);
         //println!("{:#?}", r.map);
     }
     Err("No oxygen found!".into())
@@ -214,7 +217,7 @@ fn star2() -> Result<usize, Box<dyn Error>> {
     let mut queue = VecDeque::new();
     queue.push_back(Loc::default());
     while let Some(l) = queue.pop_front() {
-        queue.extend(r.explore(l));
+        queue.extend(r.explore(l)?);
     }
 
     let oxyloc = r.map.0.values().find(|wp| wp.oxygen).unwrap().loc;
@@ -1,17 +1,16 @@
+use aoc2019::intcode::ascii::AsciiIntcode;
 use aoc2019::intcode::Intcode;
 use std::error::Error;
 
 fn scaffolding_from_file(f: &str) -> Result<Vec<Vec<char>>, Box<dyn Error>> {
-    let mut ic = Intcode::from_file(f)?;
-    let s: Vec<char> = ic
-        .run_input(&[])
+    let mut ic = AsciiIntcode::from(Intcode::from_file(f)?);
+    Ok(ic
+        .run(&[])?
         .out()
-        .iter()
-        .map(|c| *c as u8 as char)
-        .collect();
-    Ok(s.split(|&c| c == '\n')
-        .map(|v| v.to_vec())
-        .filter(|v| !v.is_empty())
+        .text
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.chars().collect())
         .collect())
 }
 
@@ -44,16 +43,16 @@ fn star1() -> Result<usize, Box<dyn Error>> {
 }
 
 fn star2() -> Result<isize, Box<dyn Error>> {
-    let input = "A,A,B,C,B,C,B,C,B,A
-L,10,L,8,R,8,L,8,R,6
-R,6,R,8,R,8
-R,6,R,6,L,8,L,10
-n
-";
-    let input: Vec<isize> = input.chars().map(|c| c as isize).collect();
-    let mut ic = Intcode::from_file("input")?;
-    *ic.mem(0) = 2;
-    Ok(*ic.run_input(&input).out().last().unwrap())
+    let input = [
+        "A,A,B,C,B,C,B,C,B,A",
+        "L,10,L,8,R,8,L,8,R,6",
+        "R,6,R,8,R,8",
+        "R,6,R,6,L,8,L,10",
+        "n",
+    ];
+    let mut ic = AsciiIntcode::from(Intcode::from_file("input")?);
+    *ic.ic.mem(0) = 2;
+    Ok(ic.run(&input)?.out().answer.ok_or("no answer")?)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -0,0 +1,1205 @@
+// Output of `to_rust` for `input`, checked in so building needs no second copy of aoc2019.
+use aoc2019::intcode::aot::{Exit, Machine};
+use aoc2019::intcode::IntcodeError;
+
+pub fn drone(
+    m: &mut Machine,
+    input: &mut dyn Iterator<Item = isize>,
+    out: &mut Vec<isize>,
+) -> Result<Exit, IntcodeError> {
+    loop {
+        match m.pc {
+            // arb #424
+            0 => {
+                if m.load(0) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(0, m.base, m.load(1))?;
+                m.pc = 2;
+            }
+            // in -> rel[+1]
+            2 => {
+                if m.load(2) != 203 {
+                    return Ok(Exit::Fallback);
+                }
+                let a = m.rel(2, 1)?;
+                match input.next() {
+                    Some(v) => m.store(a, v),
+                    None => return Ok(Exit::Wait),
+                }
+                m.pc = 4;
+            }
+            // mul #1, #11 -> rel[+0]
+            4 => {
+                if m.load(4) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(4, m.load(5), m.load(6))?;
+                let a = m.rel(4, 3)?;
+                m.store(a, v);
+                m.pc = 8;
+            }
+            // jz #0, #282
+            8 => {
+                if m.load(8) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(9) == 0 {
+                    m.load(10)
+                } else {
+                    11
+                };
+            }
+            // add #0, #18 -> rel[+0]
+            11 => {
+                if m.load(11) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(11, m.load(12), m.load(13))?;
+                let a = m.rel(11, 3)?;
+                m.store(a, v);
+                m.pc = 15;
+            }
+            // jz #0, #259
+            15 => {
+                if m.load(15) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(16) == 0 {
+                    m.load(17)
+                } else {
+                    18
+                };
+            }
+            // mul rel[+1], #1 -> [221]
+            18 => {
+                if m.load(18) != 1202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(18, m.load(m.rel(18, 1)?), m.load(20))?;
+                let a = m.pos(18, 3)?;
+                m.store(a, v);
+                m.pc = 22;
+            }
+            // in -> rel[+1]
+            22 => {
+                if m.load(22) != 203 {
+                    return Ok(Exit::Fallback);
+                }
+                let a = m.rel(22, 1)?;
+                match input.next() {
+                    Some(v) => m.store(a, v),
+                    None => return Ok(Exit::Wait),
+                }
+                m.pc = 24;
+            }
+            // add #0, #31 -> rel[+0]
+            24 => {
+                if m.load(24) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(24, m.load(25), m.load(26))?;
+                let a = m.rel(24, 3)?;
+                m.store(a, v);
+                m.pc = 28;
+            }
+            // jnz #1, #282
+            28 => {
+                if m.load(28) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(29) != 0 {
+                    m.load(30)
+                } else {
+                    31
+                };
+            }
+            // mul #38, #1 -> rel[+0]
+            31 => {
+                if m.load(31) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(31, m.load(32), m.load(33))?;
+                let a = m.rel(31, 3)?;
+                m.store(a, v);
+                m.pc = 35;
+            }
+            // jnz #1, #259
+            35 => {
+                if m.load(35) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(36) != 0 {
+                    m.load(37)
+                } else {
+                    38
+                };
+            }
+            // mul #1, [23] -> rel[+2]
+            38 => {
+                if m.load(38) != 20102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(38, m.load(39), m.load(m.pos(38, 2)?))?;
+                let a = m.rel(38, 3)?;
+                m.store(a, v);
+                m.pc = 42;
+            }
+            // add rel[+1], #0 -> rel[+3]
+            42 => {
+                if m.load(42) != 21201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(42, m.load(m.rel(42, 1)?), m.load(44))?;
+                let a = m.rel(42, 3)?;
+                m.store(a, v);
+                m.pc = 46;
+            }
+            // mul #1, #1 -> rel[+1]
+            46 => {
+                if m.load(46) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(46, m.load(47), m.load(48))?;
+                let a = m.rel(46, 3)?;
+                m.store(a, v);
+                m.pc = 50;
+            }
+            // add #0, #57 -> rel[+0]
+            50 => {
+                if m.load(50) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(50, m.load(51), m.load(52))?;
+                let a = m.rel(50, 3)?;
+                m.store(a, v);
+                m.pc = 54;
+            }
+            // jnz #1, #303
+            54 => {
+                if m.load(54) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(55) != 0 {
+                    m.load(56)
+                } else {
+                    57
+                };
+            }
+            // add #0, rel[+1] -> [222]
+            57 => {
+                if m.load(57) != 2101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(57, m.load(58), m.load(m.rel(57, 2)?))?;
+                let a = m.pos(57, 3)?;
+                m.store(a, v);
+                m.pc = 61;
+            }
+            // mul #1, [221] -> rel[+3]
+            61 => {
+                if m.load(61) != 20102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(61, m.load(62), m.load(m.pos(61, 2)?))?;
+                let a = m.rel(61, 3)?;
+                m.store(a, v);
+                m.pc = 65;
+            }
+            // mul [221], #1 -> rel[+2]
+            65 => {
+                if m.load(65) != 21002 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(65, m.load(m.pos(65, 1)?), m.load(67))?;
+                let a = m.rel(65, 3)?;
+                m.store(a, v);
+                m.pc = 69;
+            }
+            // add #0, #259 -> rel[+1]
+            69 => {
+                if m.load(69) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(69, m.load(70), m.load(71))?;
+                let a = m.rel(69, 3)?;
+                m.store(a, v);
+                m.pc = 73;
+            }
+            // add #0, #80 -> rel[+0]
+            73 => {
+                if m.load(73) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(73, m.load(74), m.load(75))?;
+                let a = m.rel(73, 3)?;
+                m.store(a, v);
+                m.pc = 77;
+            }
+            // jz #0, #225
+            77 => {
+                if m.load(77) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(78) == 0 {
+                    m.load(79)
+                } else {
+                    80
+                };
+            }
+            // mul #1, #152 -> rel[+2]
+            80 => {
+                if m.load(80) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(80, m.load(81), m.load(82))?;
+                let a = m.rel(80, 3)?;
+                m.store(a, v);
+                m.pc = 84;
+            }
+            // add #91, #0 -> rel[+0]
+            84 => {
+                if m.load(84) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(84, m.load(85), m.load(86))?;
+                let a = m.rel(84, 3)?;
+                m.store(a, v);
+                m.pc = 88;
+            }
+            // jz #0, #303
+            88 => {
+                if m.load(88) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(89) == 0 {
+                    m.load(90)
+                } else {
+                    91
+                };
+            }
+            // add rel[+1], #0 -> [223]
+            91 => {
+                if m.load(91) != 1201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(91, m.load(m.rel(91, 1)?), m.load(93))?;
+                let a = m.pos(91, 3)?;
+                m.store(a, v);
+                m.pc = 95;
+            }
+            // add [222], #0 -> rel[+4]
+            95 => {
+                if m.load(95) != 21001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(95, m.load(m.pos(95, 1)?), m.load(97))?;
+                let a = m.rel(95, 3)?;
+                m.store(a, v);
+                m.pc = 99;
+            }
+            // add #0, #259 -> rel[+3]
+            99 => {
+                if m.load(99) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(99, m.load(100), m.load(101))?;
+                let a = m.rel(99, 3)?;
+                m.store(a, v);
+                m.pc = 103;
+            }
+            // mul #225, #1 -> rel[+2]
+            103 => {
+                if m.load(103) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(103, m.load(104), m.load(105))?;
+                let a = m.rel(103, 3)?;
+                m.store(a, v);
+                m.pc = 107;
+            }
+            // add #0, #225 -> rel[+1]
+            107 => {
+                if m.load(107) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(107, m.load(108), m.load(109))?;
+                let a = m.rel(107, 3)?;
+                m.store(a, v);
+                m.pc = 111;
+            }
+            // mul #1, #118 -> rel[+0]
+            111 => {
+                if m.load(111) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(111, m.load(112), m.load(113))?;
+                let a = m.rel(111, 3)?;
+                m.store(a, v);
+                m.pc = 115;
+            }
+            // jnz #1, #225
+            115 => {
+                if m.load(115) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(116) != 0 {
+                    m.load(117)
+                } else {
+                    118
+                };
+            }
+            // add #0, [222] -> rel[+3]
+            118 => {
+                if m.load(118) != 20101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(118, m.load(119), m.load(m.pos(118, 2)?))?;
+                let a = m.rel(118, 3)?;
+                m.store(a, v);
+                m.pc = 122;
+            }
+            // mul #61, #1 -> rel[+2]
+            122 => {
+                if m.load(122) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(122, m.load(123), m.load(124))?;
+                let a = m.rel(122, 3)?;
+                m.store(a, v);
+                m.pc = 126;
+            }
+            // add #133, #0 -> rel[+0]
+            126 => {
+                if m.load(126) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(126, m.load(127), m.load(128))?;
+                let a = m.rel(126, 3)?;
+                m.store(a, v);
+                m.pc = 130;
+            }
+            // jz #0, #303
+            130 => {
+                if m.load(130) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(131) == 0 {
+                    m.load(132)
+                } else {
+                    133
+                };
+            }
+            // mul rel[+1], #-1 -> rel[+1]
+            133 => {
+                if m.load(133) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(133, m.load(m.rel(133, 1)?), m.load(135))?;
+                let a = m.rel(133, 3)?;
+                m.store(a, v);
+                m.pc = 137;
+            }
+            // add [223], rel[+1] -> rel[+1]
+            137 => {
+                if m.load(137) != 22001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(137, m.load(m.pos(137, 1)?), m.load(m.rel(137, 2)?))?;
+                let a = m.rel(137, 3)?;
+                m.store(a, v);
+                m.pc = 141;
+            }
+            // mul #148, #1 -> rel[+0]
+            141 => {
+                if m.load(141) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(141, m.load(142), m.load(143))?;
+                let a = m.rel(141, 3)?;
+                m.store(a, v);
+                m.pc = 145;
+            }
+            // jnz #1, #259
+            145 => {
+                if m.load(145) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(146) != 0 {
+                    m.load(147)
+                } else {
+                    148
+                };
+            }
+            // add #0, rel[+1] -> [223]
+            148 => {
+                if m.load(148) != 2101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(148, m.load(149), m.load(m.rel(148, 2)?))?;
+                let a = m.pos(148, 3)?;
+                m.store(a, v);
+                m.pc = 152;
+            }
+            // add [221], #0 -> rel[+4]
+            152 => {
+                if m.load(152) != 21001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(152, m.load(m.pos(152, 1)?), m.load(154))?;
+                let a = m.rel(152, 3)?;
+                m.store(a, v);
+                m.pc = 156;
+            }
+            // add [222], #0 -> rel[+3]
+            156 => {
+                if m.load(156) != 21001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(156, m.load(m.pos(156, 1)?), m.load(158))?;
+                let a = m.rel(156, 3)?;
+                m.store(a, v);
+                m.pc = 160;
+            }
+            // add #0, #14 -> rel[+2]
+            160 => {
+                if m.load(160) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(160, m.load(161), m.load(162))?;
+                let a = m.rel(160, 3)?;
+                m.store(a, v);
+                m.pc = 164;
+            }
+            // add [132], #-2 -> [224]
+            164 => {
+                if m.load(164) != 1001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(164, m.load(m.pos(164, 1)?), m.load(166))?;
+                let a = m.pos(164, 3)?;
+                m.store(a, v);
+                m.pc = 168;
+            }
+            // mul [224], #2 -> [224]
+            168 => {
+                if m.load(168) != 1002 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(168, m.load(m.pos(168, 1)?), m.load(170))?;
+                let a = m.pos(168, 3)?;
+                m.store(a, v);
+                m.pc = 172;
+            }
+            // add [224], #3 -> [224]
+            172 => {
+                if m.load(172) != 1001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(172, m.load(m.pos(172, 1)?), m.load(174))?;
+                let a = m.pos(172, 3)?;
+                m.store(a, v);
+                m.pc = 176;
+            }
+            // mul [132], #-1 -> [132]
+            176 => {
+                if m.load(176) != 1002 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(176, m.load(m.pos(176, 1)?), m.load(178))?;
+                let a = m.pos(176, 3)?;
+                m.store(a, v);
+                m.pc = 180;
+            }
+            // add [224], [132] -> [224]
+            180 => {
+                if m.load(180) != 1 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(180, m.load(m.pos(180, 1)?), m.load(m.pos(180, 2)?))?;
+                let a = m.pos(180, 3)?;
+                m.store(a, v);
+                m.pc = 184;
+            }
+            // add [224], #1 -> rel[+1]
+            184 => {
+                if m.load(184) != 21001 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(184, m.load(m.pos(184, 1)?), m.load(186))?;
+                let a = m.rel(184, 3)?;
+                m.store(a, v);
+                m.pc = 188;
+            }
+            // add #0, #195 -> rel[+0]
+            188 => {
+                if m.load(188) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(188, m.load(189), m.load(190))?;
+                let a = m.rel(188, 3)?;
+                m.store(a, v);
+                m.pc = 192;
+            }
+            // jnz #1, [109]
+            192 => {
+                if m.load(192) != 105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(193) != 0 {
+                    m.load(m.pos(192, 2)?)
+                } else {
+                    195
+                };
+            }
+            // lt rel[+1], [223] -> rel[+2]
+            195 => {
+                if m.load(195) != 20207 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.rel(195, 1)?) < m.load(m.pos(195, 2)?)) as isize;
+                let a = m.rel(195, 3)?;
+                m.store(a, v);
+                m.pc = 199;
+            }
+            // add #0, [23] -> rel[+1]
+            199 => {
+                if m.load(199) != 20101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(199, m.load(200), m.load(m.pos(199, 2)?))?;
+                let a = m.rel(199, 3)?;
+                m.store(a, v);
+                m.pc = 203;
+            }
+            // mul #-1, #1 -> rel[+3]
+            203 => {
+                if m.load(203) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(203, m.load(204), m.load(205))?;
+                let a = m.rel(203, 3)?;
+                m.store(a, v);
+                m.pc = 207;
+            }
+            // mul #214, #1 -> rel[+0]
+            207 => {
+                if m.load(207) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(207, m.load(208), m.load(209))?;
+                let a = m.rel(207, 3)?;
+                m.store(a, v);
+                m.pc = 211;
+            }
+            // jnz #1, #303
+            211 => {
+                if m.load(211) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(212) != 0 {
+                    m.load(213)
+                } else {
+                    214
+                };
+            }
+            // add #1, rel[+1] -> rel[+1]
+            214 => {
+                if m.load(214) != 22101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(214, m.load(215), m.load(m.rel(214, 2)?))?;
+                let a = m.rel(214, 3)?;
+                m.store(a, v);
+                m.pc = 218;
+            }
+            // out rel[+1]
+            218 => {
+                if m.load(218) != 204 {
+                    return Ok(Exit::Fallback);
+                }
+                out.push(m.load(m.rel(218, 1)?));
+                m.pc = 220;
+            }
+            // halt
+            220 => {
+                if m.load(220) != 99 {
+                    return Ok(Exit::Fallback);
+                }
+                return Ok(Exit::Halt);
+            }
+            // arb #5
+            225 => {
+                if m.load(225) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(225, m.base, m.load(226))?;
+                m.pc = 227;
+            }
+            // add #0, rel[-4] -> [249]
+            227 => {
+                if m.load(227) != 2101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(227, m.load(228), m.load(m.rel(227, 2)?))?;
+                let a = m.pos(227, 3)?;
+                m.store(a, v);
+                m.pc = 231;
+            }
+            // mul rel[-3], #1 -> rel[+1]
+            231 => {
+                if m.load(231) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(231, m.load(m.rel(231, 1)?), m.load(233))?;
+                let a = m.rel(231, 3)?;
+                m.store(a, v);
+                m.pc = 235;
+            }
+            // mul rel[-2], #1 -> rel[+2]
+            235 => {
+                if m.load(235) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(235, m.load(m.rel(235, 1)?), m.load(237))?;
+                let a = m.rel(235, 3)?;
+                m.store(a, v);
+                m.pc = 239;
+            }
+            // add rel[-1], #0 -> rel[+3]
+            239 => {
+                if m.load(239) != 21201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(239, m.load(m.rel(239, 1)?), m.load(241))?;
+                let a = m.rel(239, 3)?;
+                m.store(a, v);
+                m.pc = 243;
+            }
+            // mul #1, #250 -> rel[+0]
+            243 => {
+                if m.load(243) != 21102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(243, m.load(244), m.load(245))?;
+                let a = m.rel(243, 3)?;
+                m.store(a, v);
+                m.pc = 247;
+            }
+            // jz #0, #225
+            247 => {
+                if m.load(247) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(248) == 0 {
+                    m.load(249)
+                } else {
+                    250
+                };
+            }
+            // add #0, rel[+1] -> rel[-4]
+            250 => {
+                if m.load(250) != 22101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(250, m.load(251), m.load(m.rel(250, 2)?))?;
+                let a = m.rel(250, 3)?;
+                m.store(a, v);
+                m.pc = 254;
+            }
+            // arb #-5
+            254 => {
+                if m.load(254) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(254, m.base, m.load(255))?;
+                m.pc = 256;
+            }
+            // jz #0, rel[+0]
+            256 => {
+                if m.load(256) != 2106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(257) == 0 {
+                    m.load(m.rel(256, 2)?)
+                } else {
+                    259
+                };
+            }
+            // arb #3
+            259 => {
+                if m.load(259) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(259, m.base, m.load(260))?;
+                m.pc = 261;
+            }
+            // lt #0, rel[-2] -> rel[-1]
+            261 => {
+                if m.load(261) != 22107 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(262) < m.load(m.rel(261, 2)?)) as isize;
+                let a = m.rel(261, 3)?;
+                m.store(a, v);
+                m.pc = 265;
+            }
+            // mul rel[-1], #2 -> rel[-1]
+            265 => {
+                if m.load(265) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(265, m.load(m.rel(265, 1)?), m.load(267))?;
+                let a = m.rel(265, 3)?;
+                m.store(a, v);
+                m.pc = 269;
+            }
+            // add rel[-1], #-1 -> rel[-1]
+            269 => {
+                if m.load(269) != 21201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(269, m.load(m.rel(269, 1)?), m.load(271))?;
+                let a = m.rel(269, 3)?;
+                m.store(a, v);
+                m.pc = 273;
+            }
+            // mul rel[-1], rel[-2] -> rel[-2]
+            273 => {
+                if m.load(273) != 22202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(273, m.load(m.rel(273, 1)?), m.load(m.rel(273, 2)?))?;
+                let a = m.rel(273, 3)?;
+                m.store(a, v);
+                m.pc = 277;
+            }
+            // arb #-3
+            277 => {
+                if m.load(277) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(277, m.base, m.load(278))?;
+                m.pc = 279;
+            }
+            // jnz #1, rel[+0]
+            279 => {
+                if m.load(279) != 2105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(280) != 0 {
+                    m.load(m.rel(279, 2)?)
+                } else {
+                    282
+                };
+            }
+            // arb #3
+            282 => {
+                if m.load(282) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(282, m.base, m.load(283))?;
+                m.pc = 284;
+            }
+            // lt rel[-2], #0 -> rel[-1]
+            284 => {
+                if m.load(284) != 21207 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.rel(284, 1)?) < m.load(286)) as isize;
+                let a = m.rel(284, 3)?;
+                m.store(a, v);
+                m.pc = 288;
+            }
+            // jz rel[-1], #294
+            288 => {
+                if m.load(288) != 1206 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(m.rel(288, 1)?) == 0 {
+                    m.load(290)
+                } else {
+                    291
+                };
+            }
+            // out #0
+            291 => {
+                if m.load(291) != 104 {
+                    return Ok(Exit::Fallback);
+                }
+                out.push(m.load(292));
+                m.pc = 293;
+            }
+            // halt
+            293 => {
+                if m.load(293) != 99 {
+                    return Ok(Exit::Fallback);
+                }
+                return Ok(Exit::Halt);
+            }
+            // mul #1, rel[-2] -> rel[-2]
+            294 => {
+                if m.load(294) != 22102 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(294, m.load(295), m.load(m.rel(294, 2)?))?;
+                let a = m.rel(294, 3)?;
+                m.store(a, v);
+                m.pc = 298;
+            }
+            // arb #-3
+            298 => {
+                if m.load(298) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(298, m.base, m.load(299))?;
+                m.pc = 300;
+            }
+            // jnz #1, rel[+0]
+            300 => {
+                if m.load(300) != 2105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(301) != 0 {
+                    m.load(m.rel(300, 2)?)
+                } else {
+                    303
+                };
+            }
+            // arb #5
+            303 => {
+                if m.load(303) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(303, m.base, m.load(304))?;
+                m.pc = 305;
+            }
+            // lt rel[-3], rel[-4] -> rel[-1]
+            305 => {
+                if m.load(305) != 22207 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.rel(305, 1)?) < m.load(m.rel(305, 2)?)) as isize;
+                let a = m.rel(305, 3)?;
+                m.store(a, v);
+                m.pc = 309;
+            }
+            // jz rel[-1], #346
+            309 => {
+                if m.load(309) != 1206 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(m.rel(309, 1)?) == 0 {
+                    m.load(311)
+                } else {
+                    312
+                };
+            }
+            // add rel[-4], rel[-3] -> rel[-4]
+            312 => {
+                if m.load(312) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(312, m.load(m.rel(312, 1)?), m.load(m.rel(312, 2)?))?;
+                let a = m.rel(312, 3)?;
+                m.store(a, v);
+                m.pc = 316;
+            }
+            // mul rel[-3], #-1 -> rel[-1]
+            316 => {
+                if m.load(316) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(316, m.load(m.rel(316, 1)?), m.load(318))?;
+                let a = m.rel(316, 3)?;
+                m.store(a, v);
+                m.pc = 320;
+            }
+            // add rel[-4], rel[-1] -> rel[+2]
+            320 => {
+                if m.load(320) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(320, m.load(m.rel(320, 1)?), m.load(m.rel(320, 2)?))?;
+                let a = m.rel(320, 3)?;
+                m.store(a, v);
+                m.pc = 324;
+            }
+            // mul rel[+2], #-1 -> rel[-1]
+            324 => {
+                if m.load(324) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(324, m.load(m.rel(324, 1)?), m.load(326))?;
+                let a = m.rel(324, 3)?;
+                m.store(a, v);
+                m.pc = 328;
+            }
+            // add rel[-4], rel[-1] -> rel[+1]
+            328 => {
+                if m.load(328) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(328, m.load(m.rel(328, 1)?), m.load(m.rel(328, 2)?))?;
+                let a = m.rel(328, 3)?;
+                m.store(a, v);
+                m.pc = 332;
+            }
+            // mul rel[-2], #1 -> rel[+3]
+            332 => {
+                if m.load(332) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(332, m.load(m.rel(332, 1)?), m.load(334))?;
+                let a = m.rel(332, 3)?;
+                m.store(a, v);
+                m.pc = 336;
+            }
+            // add #343, #0 -> rel[+0]
+            336 => {
+                if m.load(336) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(336, m.load(337), m.load(338))?;
+                let a = m.rel(336, 3)?;
+                m.store(a, v);
+                m.pc = 340;
+            }
+            // jz #0, #303
+            340 => {
+                if m.load(340) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(341) == 0 {
+                    m.load(342)
+                } else {
+                    343
+                };
+            }
+            // jnz #1, #415
+            343 => {
+                if m.load(343) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(344) != 0 {
+                    m.load(345)
+                } else {
+                    346
+                };
+            }
+            // lt rel[-2], rel[-3] -> rel[-1]
+            346 => {
+                if m.load(346) != 22207 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.rel(346, 1)?) < m.load(m.rel(346, 2)?)) as isize;
+                let a = m.rel(346, 3)?;
+                m.store(a, v);
+                m.pc = 350;
+            }
+            // jz rel[-1], #387
+            350 => {
+                if m.load(350) != 1206 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(m.rel(350, 1)?) == 0 {
+                    m.load(352)
+                } else {
+                    353
+                };
+            }
+            // add rel[-3], rel[-2] -> rel[-3]
+            353 => {
+                if m.load(353) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(353, m.load(m.rel(353, 1)?), m.load(m.rel(353, 2)?))?;
+                let a = m.rel(353, 3)?;
+                m.store(a, v);
+                m.pc = 357;
+            }
+            // mul rel[-2], #-1 -> rel[-1]
+            357 => {
+                if m.load(357) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(357, m.load(m.rel(357, 1)?), m.load(359))?;
+                let a = m.rel(357, 3)?;
+                m.store(a, v);
+                m.pc = 361;
+            }
+            // add rel[-3], rel[-1] -> rel[+3]
+            361 => {
+                if m.load(361) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(361, m.load(m.rel(361, 1)?), m.load(m.rel(361, 2)?))?;
+                let a = m.rel(361, 3)?;
+                m.store(a, v);
+                m.pc = 365;
+            }
+            // mul rel[+3], #-1 -> rel[-1]
+            365 => {
+                if m.load(365) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(365, m.load(m.rel(365, 1)?), m.load(367))?;
+                let a = m.rel(365, 3)?;
+                m.store(a, v);
+                m.pc = 369;
+            }
+            // add rel[-3], rel[-1] -> rel[+2]
+            369 => {
+                if m.load(369) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(369, m.load(m.rel(369, 1)?), m.load(m.rel(369, 2)?))?;
+                let a = m.rel(369, 3)?;
+                m.store(a, v);
+                m.pc = 373;
+            }
+            // add #0, rel[-4] -> rel[+1]
+            373 => {
+                if m.load(373) != 22101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(373, m.load(374), m.load(m.rel(373, 2)?))?;
+                let a = m.rel(373, 3)?;
+                m.store(a, v);
+                m.pc = 377;
+            }
+            // add #0, #384 -> rel[+0]
+            377 => {
+                if m.load(377) != 21101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(377, m.load(378), m.load(379))?;
+                let a = m.rel(377, 3)?;
+                m.store(a, v);
+                m.pc = 381;
+            }
+            // jz #0, #303
+            381 => {
+                if m.load(381) != 1106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(382) == 0 {
+                    m.load(383)
+                } else {
+                    384
+                };
+            }
+            // jnz #1, #415
+            384 => {
+                if m.load(384) != 1105 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(385) != 0 {
+                    m.load(386)
+                } else {
+                    387
+                };
+            }
+            // mul rel[-4], #-1 -> rel[-4]
+            387 => {
+                if m.load(387) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(387, m.load(m.rel(387, 1)?), m.load(389))?;
+                let a = m.rel(387, 3)?;
+                m.store(a, v);
+                m.pc = 391;
+            }
+            // add rel[-4], rel[-3] -> rel[-4]
+            391 => {
+                if m.load(391) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(391, m.load(m.rel(391, 1)?), m.load(m.rel(391, 2)?))?;
+                let a = m.rel(391, 3)?;
+                m.store(a, v);
+                m.pc = 395;
+            }
+            // mul rel[-3], rel[-2] -> rel[-2]
+            395 => {
+                if m.load(395) != 22202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(395, m.load(m.rel(395, 1)?), m.load(m.rel(395, 2)?))?;
+                let a = m.rel(395, 3)?;
+                m.store(a, v);
+                m.pc = 399;
+            }
+            // mul rel[-2], rel[-4] -> rel[-4]
+            399 => {
+                if m.load(399) != 22202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(399, m.load(m.rel(399, 1)?), m.load(m.rel(399, 2)?))?;
+                let a = m.rel(399, 3)?;
+                m.store(a, v);
+                m.pc = 403;
+            }
+            // mul rel[-3], rel[-2] -> rel[-3]
+            403 => {
+                if m.load(403) != 22202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(403, m.load(m.rel(403, 1)?), m.load(m.rel(403, 2)?))?;
+                let a = m.rel(403, 3)?;
+                m.store(a, v);
+                m.pc = 407;
+            }
+            // mul rel[-4], #-1 -> rel[-2]
+            407 => {
+                if m.load(407) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(407, m.load(m.rel(407, 1)?), m.load(409))?;
+                let a = m.rel(407, 3)?;
+                m.store(a, v);
+                m.pc = 411;
+            }
+            // add rel[-3], rel[-2] -> rel[+1]
+            411 => {
+                if m.load(411) != 22201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(411, m.load(m.rel(411, 1)?), m.load(m.rel(411, 2)?))?;
+                let a = m.rel(411, 3)?;
+                m.store(a, v);
+                m.pc = 415;
+            }
+            // add rel[+1], #0 -> rel[-4]
+            415 => {
+                if m.load(415) != 21201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(415, m.load(m.rel(415, 1)?), m.load(417))?;
+                let a = m.rel(415, 3)?;
+                m.store(a, v);
+                m.pc = 419;
+            }
+            // arb #-5
+            419 => {
+                if m.load(419) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(419, m.base, m.load(420))?;
+                m.pc = 421;
+            }
+            // jz #0, rel[+0]
+            421 => {
+                if m.load(421) != 2106 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(422) == 0 {
+                    m.load(m.rel(421, 2)?)
+                } else {
+                    424
+                };
+            }
+            _ => return Ok(Exit::Fallback),
+        }
+    }
+}
@@ -1,14 +1,23 @@
-use aoc2019::intcode::Intcode;
+use aoc2019::intcode::aot::AotIntcode;
+use aoc2019::intcode::{Intcode, IntcodeError};
 use std::error::Error;
 
+#[rustfmt::skip]
+mod drone;
+
+fn load() -> Result<AotIntcode, Box<dyn Error>> {
+    let mem = Intcode::from_file("input")?.memory().into_owned();
+    Ok(AotIntcode::new(mem, drone::drone))
+}
+
 fn star1() -> Result<usize, Box<dyn Error>> {
-    let drone = Intcode::from_file("input")?;
+    let drone = load()?;
 
     let mut affected = 0;
     for y in 0..50 {
         for x in 0..50 {
             let mut ic = drone.clone();
-            if ic.run_input(&[x, y]).out()[0] == 1 {
+            if ic.run_input(&[x, y])?.out()[0] == 1 {
                 //print!("#");
                 affected += 1;
             } else {
@@ -20,11 +29,16 @@ fn star1() -> Result<usize, Box<dyn Error>> {
     Ok(affected)
 }
 
-fn find_border(drone: &Intcode, y: isize, guessx: isize, guessw: isize) -> (isize, isize) {
+fn find_border(
+    drone: &AotIntcode,
+    y: isize,
+    guessx: isize,
+    guessw: isize,
+) -> Result<(isize, isize), IntcodeError> {
     let mut width = 0;
     let mut skip = guessx;
     for x in guessx..=2 * y {
-        let c = drone.clone().run_input(&[x, y]).out()[0];
+        let c = drone.clone().run_input(&[x, y])?.out()[0];
         match c {
             0 => skip += 1,
             1 => {
@@ -36,14 +50,17 @@ fn find_border(drone: &Intcode, y: isize, guessx: isize, guessw: isize) -> (isiz
     }
 
     if width == 0 {
-        return (0, 0);
+        return Ok((0, 0));
     }
 
     width = 0.max(guessw - 1);
 
     let mut step = 1;
     loop {
-        let c = drone.clone().run_input(&[skip + width + step - 1, y]).out()[0];
+        let c = drone
+            .clone()
+            .run_input(&[skip + width + step - 1, y])?
+            .out()[0];
         match (c, step) {
             (0, 1) => break,
             (1, _) => {
@@ -57,33 +74,33 @@ fn find_border(drone: &Intcode, y: isize, guessx: isize, guessw: isize) -> (isiz
         }
     }
 
-    (skip, width)
+    Ok((skip, width))
 }
 
 fn test_fit(
-    drone: &Intcode,
+    drone: &AotIntcode,
     y: isize,
     width: isize,
     guessx: isize,
     guessw: isize,
-) -> (bool, isize, isize) {
-    let (upskip, upwidth) = find_border(drone, y, guessx, guessw);
+) -> Result<(bool, isize, isize), IntcodeError> {
+    let (upskip, upwidth) = find_border(drone, y, guessx, guessw)?;
     if upwidth < width {
-        return (false, upskip, upwidth);
+        return Ok((false, upskip, upwidth));
     }
-    let (downskip, _) = find_border(drone, y + width - 1, upskip, upwidth);
+    let (downskip, _) = find_border(drone, y + width - 1, upskip, upwidth)?;
 
-    (downskip <= upskip + upwidth - width, upskip, upwidth)
+    Ok((downskip <= upskip + upwidth - width, upskip, upwidth))
 }
 
 fn star2() -> Result<isize, Box<dyn Error>> {
-    let drone = Intcode::from_file("input")?;
+    let drone = load()?;
 
     let mut y = 0;
     let mut guessx = 0;
     let mut guessw = 0;
     loop {
-        let (fit, x, w) = test_fit(&drone, y, 100, guessx, guessw);
+        let (fit, x, w) = test_fit(&drone, y, 100, guessx, guessw)?;
         //println!("{} {}", y, fit);
         if fit {
             break;
@@ -93,7 +110,7 @@ fn star2() -> Result<isize, Box<dyn Error>> {
         guessw = w;
     }
 
-    let (upskip, upwidth) = find_border(&drone, y, 0, 0);
+    let (upskip, upwidth) = find_border(&drone, y, 0, 0)?;
 
     let x = upskip + upwidth - 100;
 
@@ -127,3 +144,16 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("Star 02: {}", star2()?);
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn drone() -> Result<(), Box<dyn Error>> {
+        let code = Intcode::from_file("input")?.to_rust("drone");
+        let src = include_str!("drone.rs");
+        assert!(src.ends_with(&code), "regenerate drone.rs:\n{}", code);
+        Ok(())
+    }
+}
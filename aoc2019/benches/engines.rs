@@ -0,0 +1,69 @@
+// Compares `Intcode` with `FastIntcode` on the day 09 BOOST run and the day 19 beam scan.
+// Run with `cargo bench`, from the aoc2019 directory.
+
+use aoc2019::intcode::Intcode;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn time<F: FnMut() -> Result<isize, Box<dyn Error>>>(
+    runs: u32,
+    mut f: F,
+) -> Result<(Duration, isize), Box<dyn Error>> {
+    let mut result = f()?;
+    let start = Instant::now();
+    for _ in 0..runs {
+        result = f()?;
+    }
+    Ok((start.elapsed() / runs, result))
+}
+
+fn bench<F, G>(name: &str, runs: u32, slow: F, fast: G) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut() -> Result<isize, Box<dyn Error>>,
+    G: FnMut() -> Result<isize, Box<dyn Error>>,
+{
+    let (slow, a) = time(runs, slow)?;
+    let (fast, b) = time(runs, fast)?;
+    assert_eq!(a, b, "{}: engines disagree", name);
+    println!(
+        "{:12} intcode {:>10.3?}  fast {:>10.3?}  speedup {:.2}x",
+        name,
+        slow,
+        fast,
+        slow.as_secs_f64() / fast.as_secs_f64()
+    );
+    Ok(())
+}
+
+fn beam<F: FnMut(isize, isize) -> Result<isize, Box<dyn Error>>>(
+    mut probe: F,
+) -> Result<isize, Box<dyn Error>> {
+    let mut affected = 0;
+    for y in 0..50 {
+        for x in 0..50 {
+            affected += probe(x, y)?;
+        }
+    }
+    Ok(affected)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let boost = Intcode::from_file("../09/input")?;
+    let boost_fast = boost.to_fast();
+    bench(
+        "day09 boost",
+        20,
+        || Ok(boost.clone().run_input(&[2])?.out()[0]),
+        || Ok(boost_fast.clone().run_input(&[2])?.out()[0]),
+    )?;
+
+    let drone = Intcode::from_file("../19/input")?;
+    let drone_fast = drone.to_fast();
+    bench(
+        "day19 beam",
+        5,
+        || beam(|x, y| Ok(drone.clone().run_input(&[x, y])?.out()[0])),
+        || beam(|x, y| Ok(drone_fast.clone().run_input(&[x, y])?.out()[0])),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+#![no_main]
+use aoc2019::intcode::fuzz::check;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte is the number of inputs, the rest are 16 bit cells with the inputs last.
+// Run with `cargo fuzz run run_input` from the aoc2019 directory.
+fuzz_target!(|data: &[u8]| {
+    let (&n, data) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let cells: Vec<isize> = data
+        .chunks_exact(2)
+        .map(|c| i16::from_le_bytes([c[0], c[1]]) as isize)
+        .collect();
+    let (code, input) = cells.split_at(cells.len() - (n as usize).min(cells.len()));
+    check(code, input);
+});
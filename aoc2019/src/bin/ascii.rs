@@ -0,0 +1,13 @@
+use aoc2019::intcode::ascii::AsciiIntcode;
+use aoc2019::intcode::Intcode;
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let f = env::args().nth(1).ok_or("usage: ascii <file>")?;
+    let mut ic = AsciiIntcode::from(Intcode::from_file(&f)?);
+    if let Some(answer) = ic.interactive()? {
+        println!("{}", answer);
+    }
+    Ok(())
+}
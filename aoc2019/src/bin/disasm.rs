@@ -0,0 +1,12 @@
+use aoc2019::intcode::Intcode;
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let f = env::args().nth(1).ok_or("usage: disasm <file>")?;
+    let ic = Intcode::from_file(&f)?;
+    for line in ic.disassemble() {
+        println!("{:5}: {}", line.addr(), line);
+    }
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use aoc2019::intcode::Intcode;
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let f = args.next().ok_or("usage: icaot <file> [name]")?;
+    let name = args.next().unwrap_or_else(|| "program".to_string());
+    let ic = Intcode::from_file(&f)?;
+    println!("use aoc2019::intcode::aot::{{Exit, Machine}};");
+    println!("use aoc2019::intcode::IntcodeError;");
+    println!();
+    print!("{}", ic.to_rust(&name));
+    Ok(())
+}
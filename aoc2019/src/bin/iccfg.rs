@@ -0,0 +1,9 @@
+use aoc2019::intcode::Intcode;
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let f = env::args().nth(1).ok_or("usage: iccfg <file>")?;
+    print!("{}", Intcode::from_file(&f)?.cfg().to_dot());
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use aoc2019::intcode::debug::{Debugger, Stop};
+use aoc2019::intcode::disasm::{decode_at, Line};
+use aoc2019::intcode::snapshot::Snapshot;
+use aoc2019::intcode::{Access, Intcode, WatchAction};
+use std::env;
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+s [n]          step n instructions
+c              continue until breakpoint, input wait or halt
+rs [n]         step back n instructions
+rw <addr>      run back to the previous write of addr
+ro             run back to the last output
+b <addr>       set breakpoint
+d <addr>       delete breakpoint
+bl             list breakpoints
+w <addr> [r|w] stop on reads or writes of addr (default: w)
+dw <addr> [r|w] delete watchpoint
+i <v>...       queue input values
+x <addr> [n]   dump n memory cells
+p <addr> <v>   poke value into memory
+r              show pc, relative base and pending input
+l [addr] [n]   disassemble n instructions (default: at pc)
+save <file>    write a snapshot of the machine and its input queue
+load <file>    restore a snapshot, breakpoints are kept
+q              quit";
+
+fn line_at(mem: &[isize], addr: usize) -> Line {
+    decode_at(mem, addr).unwrap_or_else(|| Line::Data {
+        addr,
+        values: vec![mem.get(addr).cloned().unwrap_or(0)],
+    })
+}
+
+fn list(dbg: &Debugger, addr: usize, n: usize) {
+    let mem = dbg.ic.image();
+    let mut addr = addr;
+    for _ in 0..n {
+        let line = line_at(&mem, addr);
+        let mark = if line.addr() as isize == dbg.ic.pc() {
+            "=>"
+        } else {
+            "  "
+        };
+        println!("{} {:5}: {}", mark, line.addr(), line);
+        addr += line.len();
+    }
+}
+
+fn arg<T: std::str::FromStr>(args: &[&str], i: usize) -> Result<Option<T>, Box<dyn Error>> {
+    match args.get(i) {
+        Some(a) => Ok(Some(
+            a.parse().map_err(|_| format!("invalid argument `{}`", a))?,
+        )),
+        None => Ok(None),
+    }
+}
+
+fn report(dbg: &mut Debugger, stop: Stop) {
+    for o in dbg.take_output() {
+        println!("output: {}", o);
+    }
+    match stop {
+        Stop::Step => (),
+        Stop::Breakpoint(pc) => println!("breakpoint at {}", pc),
+        Stop::Wait => println!("waiting for input"),
+        Stop::Halt(h) => println!("halted: {}", h),
+        Stop::Watch(hit) => println!(
+            "watchpoint {:?} {} at pc {}: {} -> {}",
+            hit.access, hit.addr, hit.pc, hit.old, hit.new
+        ),
+        Stop::Output(o) => println!("back at output: {}", o),
+        Stop::Start => println!("at the start of the history"),
+    }
+    list(dbg, dbg.ic.pc() as usize, 1);
+}
+
+fn command(dbg: &mut Debugger, cmd: &str, args: &[&str]) -> Result<bool, Box<dyn Error>> {
+    match cmd {
+        "s" => {
+            let mut stop = Stop::Step;
+            for _ in 0..arg(args, 0)?.unwrap_or(1) {
+                stop = dbg.step()?;
+                if stop != Stop::Step {
+                    break;
+                }
+            }
+            report(dbg, stop);
+        }
+        "c" => {
+            let stop = dbg.cont()?;
+            report(dbg, stop);
+        }
+        "rs" => {
+            let mut stop = Stop::Step;
+            for _ in 0..arg(args, 0)?.unwrap_or(1) {
+                stop = dbg.step_back()?;
+                if stop != Stop::Step {
+                    break;
+                }
+            }
+            report(dbg, stop);
+        }
+        "rw" => {
+            let stop = dbg.back_to_write(arg(args, 0)?.ok_or("address needed")?)?;
+            report(dbg, stop);
+        }
+        "ro" => {
+            let stop = dbg.back_to_output()?;
+            report(dbg, stop);
+        }
+        "b" | "d" => {
+            let addr = arg(args, 0)?.ok_or("address needed")?;
+            if cmd == "b" {
+                dbg.add_breakpoint(addr);
+            } else if !dbg.remove_breakpoint(addr) {
+                println!("no breakpoint at {}", addr);
+            }
+        }
+        "bl" => {
+            for b in dbg.breakpoints() {
+                println!("{:5}", b);
+            }
+        }
+        "w" | "dw" => {
+            let addr = arg(args, 0)?.ok_or("address needed")?;
+            let access = match args.get(1) {
+                Some(&"r") => Access::Read,
+                Some(&"w") | None => Access::Write,
+                Some(a) => return Err(format!("invalid access `{}`", a).into()),
+            };
+            if cmd == "w" {
+                dbg.ic.watch(addr, access, WatchAction::Stop);
+            } else if !dbg.ic.unwatch(addr, access) {
+                println!("no watchpoint at {}", addr);
+            }
+        }
+        "i" => {
+            let input = args
+                .iter()
+                .map(|a| a.parse())
+                .collect::<Result<Vec<isize>, _>>()?;
+            dbg.feed(&input);
+        }
+        "x" => {
+            let addr: usize = arg(args, 0)?.ok_or("address needed")?;
+            let n: usize = arg(args, 1)?.unwrap_or(8);
+            for row in (addr..addr + n).collect::<Vec<_>>().chunks(8) {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|&a| format!("{:>8}", dbg.ic.peek(a)))
+                    .collect();
+                println!("{:5}: {}", row[0], cells.join(" "));
+            }
+        }
+        "p" => {
+            let addr = arg(args, 0)?.ok_or("address needed")?;
+            dbg.poke(addr, arg(args, 1)?.ok_or("value needed")?)?;
+        }
+        "r" => {
+            println!("pc:   {}", dbg.ic.pc());
+            println!("base: {}", dbg.ic.base());
+            println!("time: {}", dbg.time());
+            println!("input: {:?}", dbg.pending_input().collect::<Vec<_>>());
+            list(dbg, dbg.ic.pc() as usize, 1);
+        }
+        "l" => {
+            let addr = arg(args, 0)?.unwrap_or(dbg.ic.pc() as usize);
+            list(dbg, addr, arg(args, 1)?.unwrap_or(10));
+        }
+        "save" => {
+            dbg.snapshot().save(args.first().ok_or("file needed")?)?;
+        }
+        "load" => {
+            let snapshot = Snapshot::load(args.first().ok_or("file needed")?)?;
+            let breakpoints: Vec<_> = dbg.breakpoints().collect();
+            *dbg = Debugger::from_snapshot(snapshot);
+            for b in breakpoints {
+                dbg.add_breakpoint(b);
+            }
+            list(dbg, dbg.ic.pc() as usize, 1);
+        }
+        "q" => return Ok(false),
+        _ => println!("{}", HELP),
+    }
+    Ok(true)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let f = env::args().nth(1).ok_or("usage: icdb <file>")?;
+    let mut dbg = Debugger::new(Intcode::from_file(&f)?);
+    list(&dbg, 0, 1);
+
+    let stdin = io::stdin();
+    let mut last = String::new();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            line = last.clone();
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        match command(&mut dbg, words[0], &words[1..]) {
+            Ok(true) => (),
+            Ok(false) => return Ok(()),
+            Err(e) => println!("error: {}", e),
+        }
+        last = line;
+    }
+}
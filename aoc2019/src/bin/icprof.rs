@@ -0,0 +1,21 @@
+use aoc2019::intcode::Intcode;
+use std::env;
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let f = args.next().ok_or("usage: icprof <file> [input,...]")?;
+    let input = match args.next() {
+        Some(s) => s
+            .split(',')
+            .map(|v| v.trim().parse())
+            .collect::<Result<Vec<isize>, _>>()?,
+        None => Vec::new(),
+    };
+    let mut ic = Intcode::from_file(&f)?;
+    ic.start_profile();
+    let out = ic.run_input(&input)?;
+    println!("output: {:?}\n", out.out());
+    print!("{}", ic.stop_profile().unwrap().report(&ic.image(), 20));
+    Ok(())
+}
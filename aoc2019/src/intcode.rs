@@ -0,0 +1,565 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+pub mod aot;
+pub mod ascii;
+pub mod asm;
+mod budget;
+pub mod cfg;
+mod codec;
+#[cfg(test)]
+mod conformance;
+pub mod debug;
+mod decode;
+pub mod decompile;
+pub mod disasm;
+pub mod fast;
+pub mod fuzz;
+mod history;
+pub mod memory;
+pub mod network;
+pub mod profile;
+pub mod sched;
+pub mod snapshot;
+mod stream;
+pub mod symbolic;
+pub mod topology;
+pub mod trace;
+mod watch;
+pub mod wide;
+
+pub use decode::{Instr, Mode, Opcode, Operand};
+pub use memory::{Dense, Memory, Paged};
+pub use stream::{IntcodeInput, IntcodeOutput};
+pub use watch::{Access, WatchAction, WatchHit};
+pub use wide::Cell;
+
+/// Default for `Intcode::set_address_limit`, far above anything the puzzles touch.
+pub const ADDRESS_LIMIT: usize = 1 << 24;
+
+/// Interpreter over cells of type `C`. Arithmetic overflow fails with
+/// `IntcodeError::Overflow` unless wrapping is enabled, as does any address or opcode that
+/// does not fit into an `isize`.
+#[derive(Clone)]
+pub struct Intcode<C: Cell = isize> {
+    mem: Box<dyn Memory<C>>,
+    pc: isize,
+    base: isize,
+    limit: usize,
+    wrap: bool,
+    watch: watch::Watchpoints<C>,
+    tracer: Option<Box<trace::Tracer<C>>>,
+    budget: budget::Budget,
+    profile: Option<Box<profile::Profile>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntcodeError {
+    InvalidOpcode {
+        pc: isize,
+        instr: isize,
+    },
+    InvalidMode {
+        pc: isize,
+        instr: isize,
+        mode: isize,
+    },
+    NegativeAddress {
+        pc: isize,
+        instr: isize,
+        addr: isize,
+    },
+    InvalidPc {
+        pc: isize,
+    },
+    /// An address at or above the machine's address limit.
+    AddressTooLarge {
+        pc: isize,
+        addr: isize,
+    },
+    InputStarved {
+        pc: isize,
+        instr: isize,
+    },
+    BudgetExhausted {
+        pc: isize,
+    },
+    /// A `WatchAction::Stop` watchpoint ended `run` after the instruction at `pc`, the hit
+    /// is left for `take_watch_stop`.
+    Watchpoint {
+        pc: isize,
+        addr: usize,
+    },
+    /// A result, address or opcode does not fit into a cell.
+    Overflow {
+        pc: isize,
+    },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::InvalidOpcode { pc, instr } => {
+                write!(
+                    f,
+                    "invalid opcode {}, pc: {}, instr: {}",
+                    instr % 100,
+                    pc,
+                    instr
+                )
+            }
+            IntcodeError::InvalidMode { pc, instr, mode } => write!(
+                f,
+                "unknown parameter mode {}, pc: {}, instr: {}",
+                mode, pc, instr
+            ),
+            IntcodeError::NegativeAddress { pc, instr, addr } => {
+                write!(f, "negative address {}, pc: {}, instr: {}", addr, pc, instr)
+            }
+            IntcodeError::InvalidPc { pc } => write!(f, "invalid pc: {}", pc),
+            IntcodeError::AddressTooLarge { pc, addr } => {
+                write!(f, "address {} too large, pc: {}", addr, pc)
+            }
+            IntcodeError::InputStarved { pc, instr } => {
+                write!(f, "input needed, pc: {}, instr: {}", pc, instr)
+            }
+            IntcodeError::BudgetExhausted { pc } => write!(f, "budget exhausted, pc: {}", pc),
+            IntcodeError::Watchpoint { pc, addr } => {
+                write!(f, "watchpoint at {} hit, pc: {}", addr, pc)
+            }
+            IntcodeError::Overflow { pc } => write!(f, "arithmetic overflow, pc: {}", pc),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
+
+pub enum InstrState<C = isize> {
+    Run,
+    Wait,
+    Output(C),
+    Halt(C),
+}
+
+/// `C` is the type of the halt code, the first memory cell.
+#[derive(Debug, PartialEq)]
+pub enum State<T, C = isize> {
+    Wait(T),
+    Halt(T, C),
+    Watch(T, WatchHit<C>),
+    /// The instruction budget or deadline ran out before the instruction at the pc.
+    Budget(T, isize),
+}
+
+impl<T, C> State<T, C> {
+    pub fn out(&self) -> &T {
+        match self {
+            State::Wait(t) => t,
+            State::Halt(t, _) => t,
+            State::Watch(t, _) => t,
+            State::Budget(t, _) => t,
+        }
+    }
+    pub fn is_halt(&self) -> bool {
+        matches!(self, State::Halt(_, _))
+    }
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> State<U, C> {
+        match self {
+            State::Wait(t) => State::Wait(f(t)),
+            State::Halt(t, h) => State::Halt(f(t), h),
+            State::Watch(t, hit) => State::Watch(f(t), hit),
+            State::Budget(t, pc) => State::Budget(f(t), pc),
+        }
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    fn load(&self, addr: isize) -> C {
+        if addr < 0 {
+            return C::default();
+        }
+        self.mem.get(addr as usize)
+    }
+    /// The value as an address, opcode or offset, `Overflow` if it does not fit.
+    fn small(&self, v: C) -> Result<isize, IntcodeError> {
+        v.to_isize().ok_or(IntcodeError::Overflow { pc: self.pc })
+    }
+    // Only used once the instruction decoded, so it always fits.
+    fn instr(&self) -> isize {
+        self.load(self.pc).to_isize().unwrap_or_default()
+    }
+    fn op(&self) -> Result<Instr, IntcodeError> {
+        // Leaves room for the parameters, so advancing the pc can not overflow.
+        if self.pc < 0 || self.pc > isize::MAX - 4 {
+            return Err(IntcodeError::InvalidPc { pc: self.pc });
+        }
+        Instr::decode(self.pc, self.small(self.load(self.pc))?)
+    }
+    pub fn ind_mut(&mut self, off: isize) -> Result<&mut C, IntcodeError> {
+        let addr = self.param_addr(off, Mode::Position)?;
+        Ok(self.mem.get_mut(addr))
+    }
+    pub fn param_addr(&mut self, off: isize, mode: Mode) -> Result<usize, IntcodeError> {
+        let immaddr = self.pc + off;
+        let addr = match mode {
+            Mode::Position => self.small(self.load(immaddr))?,
+            Mode::Immediate => immaddr,
+            Mode::Relative => self
+                .small(self.load(immaddr))?
+                .checked_add(self.base)
+                .ok_or(IntcodeError::Overflow { pc: self.pc })?,
+        };
+        if addr < 0 {
+            return Err(IntcodeError::NegativeAddress {
+                pc: self.pc,
+                instr: self.instr(),
+                addr,
+            });
+        }
+        if addr as usize >= self.limit {
+            return Err(IntcodeError::AddressTooLarge { pc: self.pc, addr });
+        }
+        Ok(addr as usize)
+    }
+    fn read(&mut self, addr: usize) -> C {
+        let v = self.mem.get(addr);
+        self.check_watch(addr, Access::Read, &v, &v);
+        if let Some(t) = &mut self.tracer {
+            t.read(v.clone());
+        }
+        v
+    }
+    fn write(&mut self, addr: usize, v: C) {
+        let old = std::mem::replace(self.mem.get_mut(addr), v);
+        if !self.watch.is_empty() || self.tracer.is_some() {
+            let new = self.mem.get(addr);
+            self.check_watch(addr, Access::Write, &old, &new);
+            if let Some(t) = &mut self.tracer {
+                t.write(addr, new);
+            }
+        }
+    }
+    pub fn param(&mut self, off: isize, mode: Mode) -> Result<C, IntcodeError> {
+        let addr = self.param_addr(off, mode)?;
+        Ok(self.read(addr))
+    }
+    pub fn store(&mut self, off: isize, mode: Mode, v: C) -> Result<(), IntcodeError> {
+        let addr = self.param_addr(off, mode)?;
+        self.write(addr, v);
+        Ok(())
+    }
+    pub fn step(
+        &mut self,
+        input: &mut dyn Iterator<Item = C>,
+    ) -> Result<InstrState<C>, IntcodeError> {
+        let (pc, instr) = (self.pc, self.instr());
+        if let Some(t) = &mut self.tracer {
+            t.begin(pc, instr);
+        }
+        let state = self.exec(input)?;
+        if let Some(t) = &mut self.tracer {
+            t.end(&state);
+        }
+        if let Some(p) = &mut self.profile {
+            if !matches!(state, InstrState::Wait) {
+                p.record(pc, instr, self.mem.len());
+            }
+        }
+        Ok(state)
+    }
+    fn exec(&mut self, input: &mut dyn Iterator<Item = C>) -> Result<InstrState<C>, IntcodeError> {
+        let Instr {
+            op,
+            modes: [p1, p2, p3],
+        } = self.op()?;
+        Ok(match op {
+            Opcode::Add => {
+                let v = self.param(1, p1)?.plus(&self.param(2, p2)?, self.wrap);
+                let v = v.ok_or(IntcodeError::Overflow { pc: self.pc })?;
+                self.store(3, p3, v)?;
+                self.pc += 4;
+                InstrState::Run
+            }
+            Opcode::Mul => {
+                let v = self.param(1, p1)?.times(&self.param(2, p2)?, self.wrap);
+                let v = v.ok_or(IntcodeError::Overflow { pc: self.pc })?;
+                self.store(3, p3, v)?;
+                self.pc += 4;
+                InstrState::Run
+            }
+            Opcode::In => {
+                let addr = self.param_addr(1, p1)?;
+                if let Some(input) = input.next() {
+                    self.write(addr, input);
+                    self.pc += 2;
+                    InstrState::Run
+                } else {
+                    InstrState::Wait
+                }
+            }
+            Opcode::Out => {
+                let data = self.param(1, p1)?;
+                self.pc += 2;
+                InstrState::Output(data)
+            }
+            Opcode::Jnz => {
+                if self.param(1, p1)? == C::default() {
+                    self.pc += 3;
+                } else {
+                    let target = self.param(2, p2)?;
+                    self.pc = self.small(target)?;
+                }
+                InstrState::Run
+            }
+            Opcode::Jz => {
+                if self.param(1, p1)? == C::default() {
+                    let target = self.param(2, p2)?;
+                    self.pc = self.small(target)?;
+                } else {
+                    self.pc += 3;
+                }
+                InstrState::Run
+            }
+            Opcode::Lt => {
+                let v = C::from_isize((self.param(1, p1)? < self.param(2, p2)?) as isize);
+                self.store(3, p3, v)?;
+                self.pc += 4;
+                InstrState::Run
+            }
+            Opcode::Eq => {
+                let v = C::from_isize((self.param(1, p1)? == self.param(2, p2)?) as isize);
+                self.store(3, p3, v)?;
+                self.pc += 4;
+                InstrState::Run
+            }
+            Opcode::Arb => {
+                let off = self.param(1, p1)?;
+                let base = self.base.checked_add(self.small(off)?);
+                self.base = base.ok_or(IntcodeError::Overflow { pc: self.pc })?;
+                self.pc += 2;
+                InstrState::Run
+            }
+            Opcode::Halt => InstrState::Halt(self.load(0)),
+        })
+    }
+    pub fn run(&mut self) -> Result<C, IntcodeError> {
+        self.budget.start();
+        self.watch.clear_stop();
+        loop {
+            if self.budget.exhausted() {
+                return Err(IntcodeError::BudgetExhausted { pc: self.pc });
+            }
+            match self.step(&mut std::iter::empty())? {
+                InstrState::Run => (),
+                InstrState::Wait => {
+                    return Err(IntcodeError::InputStarved {
+                        pc: self.pc,
+                        instr: self.instr(),
+                    })
+                }
+                InstrState::Output(_) => (),
+                InstrState::Halt(x) => return Ok(x),
+            }
+            self.budget.charge();
+            if let Some(hit) = &self.watch.stop {
+                return Err(IntcodeError::Watchpoint {
+                    pc: hit.pc,
+                    addr: hit.addr,
+                });
+            }
+        }
+    }
+    /// Runs until the program halts, `input` runs dry, a watchpoint stops execution or the
+    /// budget is exhausted.
+    pub fn run_with<I, O>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<State<(), C>, IntcodeError>
+    where
+        I: IntcodeInput<C> + ?Sized,
+        O: IntcodeOutput<C> + ?Sized,
+    {
+        let mut in_iter = std::iter::from_fn(|| input.input());
+        self.budget.start();
+        self.watch.clear_stop();
+        loop {
+            if self.budget.exhausted() {
+                return Ok(State::Budget((), self.pc));
+            }
+            match self.step(&mut in_iter)? {
+                InstrState::Run => (),
+                InstrState::Wait => return Ok(State::Wait(())),
+                InstrState::Output(o) => output.output(o),
+                InstrState::Halt(h) => return Ok(State::Halt((), h)),
+            }
+            self.budget.charge();
+            if let Some(hit) = self.take_watch_stop() {
+                return Ok(State::Watch((), hit));
+            }
+        }
+    }
+    pub fn run_input(&mut self, input: &[C]) -> Result<State<Vec<C>, C>, IntcodeError> {
+        let mut out = Vec::new();
+        let state = self.run_with(&mut input.iter(), &mut out)?;
+        Ok(state.map(|_| out))
+    }
+    pub fn set(&mut self, noun: C, verb: C) {
+        *self.mem.get_mut(1) = noun;
+        *self.mem.get_mut(2) = verb;
+    }
+    pub fn mem(&mut self, idx: usize) -> &mut C {
+        self.mem.get_mut(idx)
+    }
+    /// Value at `idx` without allocating it.
+    pub fn peek(&self, idx: usize) -> C {
+        self.mem.get(idx)
+    }
+    /// All cells up to the highest one written. Sparse backends are copied in full, use
+    /// `image` for the program and `peek` for single cells.
+    pub fn memory(&self) -> Cow<'_, [C]> {
+        match self.mem.as_slice() {
+            Some(mem) => Cow::Borrowed(mem),
+            None => Cow::Owned(self.mem.to_vec()),
+        }
+    }
+    /// Cells from address 0 up to the first gap in allocated memory, the program image
+    /// static analyses work on. Far away sparse writes are not copied.
+    pub fn image(&self) -> Cow<'_, [C]> {
+        if let Some(mem) = self.mem.as_slice() {
+            return Cow::Borrowed(mem);
+        }
+        let mut image = Vec::new();
+        for (start, cells) in self.mem.runs() {
+            if start != image.len() {
+                break;
+            }
+            image.extend_from_slice(cells);
+        }
+        Cow::Owned(image)
+    }
+    /// Addresses at or above `limit` fail with `AddressTooLarge` instead of growing memory.
+    /// Defaults to `ADDRESS_LIMIT`.
+    pub fn set_address_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+    pub fn address_limit(&self) -> usize {
+        self.limit
+    }
+    /// Wrap around on arithmetic overflow instead of failing with `Overflow`. Has no
+    /// effect on unbounded cells.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+    pub fn memory_len(&self) -> usize {
+        self.mem.len()
+    }
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+    pub fn base(&self) -> isize {
+        self.base
+    }
+    /// Creates a machine on the given memory backend, `From<Vec<C>>` uses `Dense`.
+    pub fn with_memory<M: Memory<C> + 'static>(mem: M) -> Self {
+        Intcode::from_box(Box::new(mem))
+    }
+    fn from_box(mem: Box<dyn Memory<C>>) -> Self {
+        Intcode {
+            mem,
+            pc: 0,
+            base: 0,
+            limit: ADDRESS_LIMIT,
+            wrap: false,
+            watch: Default::default(),
+            tracer: None,
+            budget: Default::default(),
+            profile: None,
+        }
+    }
+}
+
+impl Intcode {
+    pub fn from_file(f: &str) -> Result<Self, Box<dyn Error>> {
+        super::lines(f)?
+            .next()
+            .ok_or_else(|| "no code available".to_string())?
+            .parse()
+    }
+}
+
+impl FromStr for Intcode {
+    type Err = Box<dyn Error>;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut mem: Vec<isize> = src
+            .split(',')
+            .map(|n| n.parse())
+            .collect::<Result<_, _>>()?;
+        mem.reserve(mem.len() * 2);
+        Ok(Intcode::from(mem))
+    }
+}
+
+impl<C: Cell> From<Vec<C>> for Intcode<C> {
+    fn from(mem: Vec<C>) -> Self {
+        Intcode::with_memory(Dense::from(mem))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn errors() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            Err(IntcodeError::InvalidOpcode { pc: 4, instr: 42 }),
+            Intcode::from_str("1101,1,1,0,42")?.run()
+        );
+        assert_eq!(
+            Err(IntcodeError::InvalidMode {
+                pc: 0,
+                instr: 301,
+                mode: 3
+            }),
+            Intcode::from_str("301,0,0,0,99")?.run()
+        );
+        assert_eq!(
+            Err(IntcodeError::NegativeAddress {
+                pc: 0,
+                instr: 1,
+                addr: -1
+            }),
+            Intcode::from_str("1,-1,0,0,99")?.run()
+        );
+        assert_eq!(
+            Err(IntcodeError::InvalidPc { pc: -7 }),
+            Intcode::from_str("1105,1,-7")?.run()
+        );
+        assert_eq!(
+            Err(IntcodeError::InputStarved { pc: 0, instr: 3 }),
+            Intcode::from_str("3,0,99")?.run()
+        );
+        assert_eq!(
+            Err(IntcodeError::AddressTooLarge {
+                pc: 0,
+                addr: 100_000_000_000_000_000
+            }),
+            Intcode::from_str("1101,1,1,100000000000000000,99")?.run()
+        );
+        Ok(())
+    }
+    #[test]
+    fn run_past_end() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            Err(IntcodeError::InvalidOpcode { pc: 1000, instr: 0 }),
+            Intcode::from_str("1105,1,1000")?.run_input(&[])
+        );
+        Ok(())
+    }
+}
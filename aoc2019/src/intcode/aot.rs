@@ -0,0 +1,358 @@
+use super::disasm::{disassemble, Line};
+use super::wide::Cell;
+use super::{Intcode, IntcodeError, Mode, Opcode, Operand, State, ADDRESS_LIMIT};
+use std::fmt::Write;
+
+/// Registers and memory of a compiled program, used by the generated code.
+#[derive(Debug, Clone)]
+pub struct Machine {
+    pub mem: Vec<isize>,
+    pub pc: isize,
+    pub base: isize,
+    /// Same as `Intcode::set_address_limit`.
+    pub limit: usize,
+    /// Same as `Intcode::set_wrap`.
+    pub wrap: bool,
+}
+
+impl Machine {
+    #[inline]
+    pub fn load(&self, addr: usize) -> isize {
+        self.mem.get(addr).cloned().unwrap_or(0)
+    }
+    #[inline]
+    pub fn store(&mut self, addr: usize, v: isize) {
+        if addr >= self.mem.len() {
+            self.mem.resize(addr + 1, 0);
+        }
+        self.mem[addr] = v;
+    }
+    fn check(&self, pc: usize, addr: isize) -> Result<usize, IntcodeError> {
+        if addr < 0 {
+            return Err(IntcodeError::NegativeAddress {
+                pc: pc as isize,
+                instr: self.load(pc),
+                addr,
+            });
+        }
+        if addr as usize >= self.limit {
+            return Err(IntcodeError::AddressTooLarge {
+                pc: pc as isize,
+                addr,
+            });
+        }
+        Ok(addr as usize)
+    }
+    /// Address of a position mode parameter.
+    #[inline]
+    pub fn pos(&self, pc: usize, off: usize) -> Result<usize, IntcodeError> {
+        self.check(pc, self.load(pc + off))
+    }
+    /// Address of a relative mode parameter.
+    #[inline]
+    pub fn rel(&self, pc: usize, off: usize) -> Result<usize, IntcodeError> {
+        let addr = self.load(pc + off).checked_add(self.base);
+        self.check(pc, addr.ok_or(IntcodeError::Overflow { pc: pc as isize })?)
+    }
+    #[inline]
+    pub fn add(&self, pc: usize, a: isize, b: isize) -> Result<isize, IntcodeError> {
+        a.plus(&b, self.wrap)
+            .ok_or(IntcodeError::Overflow { pc: pc as isize })
+    }
+    #[inline]
+    pub fn mul(&self, pc: usize, a: isize, b: isize) -> Result<isize, IntcodeError> {
+        a.times(&b, self.wrap)
+            .ok_or(IntcodeError::Overflow { pc: pc as isize })
+    }
+}
+
+/// Why compiled code returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Wait,
+    Halt,
+    /// The instruction at the pc was not compiled or has been overwritten.
+    Fallback,
+}
+
+/// Signature of the functions `compile` generates.
+pub type Compiled = fn(
+    &mut Machine,
+    &mut dyn Iterator<Item = isize>,
+    &mut Vec<isize>,
+) -> Result<Exit, IntcodeError>;
+
+fn read(addr: usize, i: usize, arg: &Operand) -> String {
+    match arg.mode {
+        Mode::Position => format!("m.load(m.pos({}, {})?)", addr, i),
+        Mode::Immediate => format!("m.load({})", addr + i),
+        Mode::Relative => format!("m.load(m.rel({}, {})?)", addr, i),
+    }
+}
+
+fn dest(addr: usize, i: usize, arg: &Operand) -> String {
+    match arg.mode {
+        Mode::Position => format!("m.pos({}, {})?", addr, i),
+        Mode::Immediate => format!("{}", addr + i),
+        Mode::Relative => format!("m.rel({}, {})?", addr, i),
+    }
+}
+
+fn body(addr: usize, op: Opcode, args: &[Operand]) -> Vec<String> {
+    let r = |i: usize| read(addr, i, &args[i - 1]);
+    let next = addr + op.params() + 1;
+    let binary = |expr: String| {
+        vec![
+            format!("let v = {};", expr),
+            format!("let a = {};", dest(addr, 3, &args[2])),
+            "m.store(a, v);".to_string(),
+            format!("m.pc = {};", next),
+        ]
+    };
+    match op {
+        Opcode::Add => binary(format!("m.add({}, {}, {})?", addr, r(1), r(2))),
+        Opcode::Mul => binary(format!("m.mul({}, {}, {})?", addr, r(1), r(2))),
+        Opcode::Lt => binary(format!("({} < {}) as isize", r(1), r(2))),
+        Opcode::Eq => binary(format!("({} == {}) as isize", r(1), r(2))),
+        Opcode::In => vec![
+            format!("let a = {};", dest(addr, 1, &args[0])),
+            "match input.next() {".to_string(),
+            "    Some(v) => m.store(a, v),".to_string(),
+            "    None => return Ok(Exit::Wait),".to_string(),
+            "}".to_string(),
+            format!("m.pc = {};", next),
+        ],
+        Opcode::Out => vec![format!("out.push({});", r(1)), format!("m.pc = {};", next)],
+        Opcode::Jnz | Opcode::Jz => vec![
+            format!(
+                "m.pc = if {} {} 0 {{",
+                r(1),
+                if op == Opcode::Jnz { "!=" } else { "==" }
+            ),
+            format!("    {}", r(2)),
+            "} else {".to_string(),
+            format!("    {}", next),
+            "};".to_string(),
+        ],
+        Opcode::Arb => vec![
+            format!("m.base = m.add({}, m.base, {})?;", addr, r(1)),
+            format!("m.pc = {};", next),
+        ],
+        Opcode::Halt => vec!["return Ok(Exit::Halt);".to_string()],
+    }
+}
+
+/// Generates a function `name` with the `Compiled` signature that executes `mem`. Every
+/// instruction found by a linear sweep gets its own match arm with the opcode and modes
+/// resolved; parameters are still read from memory, so only overwriting an opcode itself
+/// or jumping between the decoded instructions leaves compiled code. The caller has to
+/// bring `Exit`, `Machine` and `IntcodeError` into scope.
+pub fn compile(mem: &[isize], name: &str) -> String {
+    let mut s = String::new();
+    writeln!(s, "pub fn {}(", name).unwrap();
+    writeln!(s, "    m: &mut Machine,").unwrap();
+    writeln!(s, "    input: &mut dyn Iterator<Item = isize>,").unwrap();
+    writeln!(s, "    out: &mut Vec<isize>,").unwrap();
+    writeln!(s, ") -> Result<Exit, IntcodeError> {{").unwrap();
+    writeln!(s, "    loop {{").unwrap();
+    writeln!(s, "        match m.pc {{").unwrap();
+    for line in disassemble(mem) {
+        if let Line::Instr { addr, op, ref args } = line {
+            writeln!(s, "            // {}", line).unwrap();
+            writeln!(s, "            {} => {{", addr).unwrap();
+            writeln!(s, "                if m.load({}) != {} {{", addr, mem[addr]).unwrap();
+            writeln!(s, "                    return Ok(Exit::Fallback);").unwrap();
+            writeln!(s, "                }}").unwrap();
+            for l in body(addr, op, args) {
+                writeln!(s, "                {}", l).unwrap();
+            }
+            writeln!(s, "            }}").unwrap();
+        }
+    }
+    writeln!(s, "            _ => return Ok(Exit::Fallback),").unwrap();
+    writeln!(s, "        }}").unwrap();
+    writeln!(s, "    }}").unwrap();
+    writeln!(s, "}}").unwrap();
+    s
+}
+
+/// Runs a program through its compiled form, switching to the interpreter for good once
+/// compiled code can not continue.
+#[derive(Clone)]
+pub struct AotIntcode {
+    m: Machine,
+    code: Compiled,
+    fallback: Option<Intcode>,
+}
+
+impl AotIntcode {
+    /// `mem` should be the image `code` was compiled from, but any image works: compiled
+    /// code falls back to the interpreter when an opcode differs.
+    pub fn new(mem: Vec<isize>, code: Compiled) -> Self {
+        AotIntcode {
+            m: Machine {
+                mem,
+                pc: 0,
+                base: 0,
+                limit: ADDRESS_LIMIT,
+                wrap: false,
+            },
+            code,
+            fallback: None,
+        }
+    }
+    /// Whether execution moved to the interpreter.
+    pub fn is_fallback(&self) -> bool {
+        self.fallback.is_some()
+    }
+    pub fn set_address_limit(&mut self, limit: usize) {
+        self.m.limit = limit;
+        if let Some(ic) = &mut self.fallback {
+            ic.limit = limit;
+        }
+    }
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.m.wrap = wrap;
+        if let Some(ic) = &mut self.fallback {
+            ic.wrap = wrap;
+        }
+    }
+    pub fn mem(&mut self, idx: usize) -> &mut isize {
+        match &mut self.fallback {
+            Some(ic) => ic.mem(idx),
+            None => {
+                if idx >= self.m.mem.len() {
+                    self.m.mem.resize(idx + 1, 0);
+                }
+                &mut self.m.mem[idx]
+            }
+        }
+    }
+    pub fn run_input(&mut self, input: &[isize]) -> Result<State<Vec<isize>>, IntcodeError> {
+        if let Some(ic) = &mut self.fallback {
+            return ic.run_input(input);
+        }
+        let mut input = input.iter().cloned();
+        let mut out = Vec::new();
+        match (self.code)(&mut self.m, &mut input, &mut out)? {
+            Exit::Wait => Ok(State::Wait(out)),
+            Exit::Halt => Ok(State::Halt(out, self.m.load(0))),
+            Exit::Fallback => {
+                let mut ic = Intcode::from(std::mem::take(&mut self.m.mem));
+                ic.pc = self.m.pc;
+                ic.base = self.m.base;
+                ic.limit = self.m.limit;
+                ic.wrap = self.m.wrap;
+                let rest: Vec<_> = input.collect();
+                let state = ic.run_input(&rest);
+                self.fallback = Some(ic);
+                Ok(state?.map(|o| {
+                    out.extend(o);
+                    out
+                }))
+            }
+        }
+    }
+}
+
+impl Intcode {
+    /// Rust source of the program image, see `compile`.
+    pub fn to_rust(&self, name: &str) -> String {
+        compile(&self.image(), name)
+    }
+}
+
+#[cfg(test)]
+#[path = "aot_golden.rs"]
+mod golden;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    // Every opcode and mode. An input of 8 patches the final halt into an output, which
+    // moves execution to the interpreter.
+    const PROGRAM: &str = "
+                in -> [x]
+                eq [x], #8 -> [y]
+                out [y]
+                arb #x
+        loop:   mul rel[0], #2 -> rel[0]
+                add rel[0], #1 -> rel[0]
+                lt rel[0], #100 -> [z]
+                jnz [z], #loop
+                out rel[0]
+                jz [y], #done
+                add #104, #0 -> [done]
+        done:   halt
+                data 7
+                halt
+        x:      data 0
+        y:      data 0
+        z:      data 0
+    ";
+
+    #[test]
+    fn golden() -> Result<(), Box<dyn Error>> {
+        let code = Intcode::from_asm(PROGRAM)?.to_rust("program");
+        let src = include_str!("aot_golden.rs");
+        assert!(src.ends_with(&code), "regenerate aot_golden.rs:\n{}", code);
+        Ok(())
+    }
+    #[test]
+    fn conformance() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_asm(PROGRAM)?;
+        for &(input, fallback) in &[(8, true), (3, false), (0, false)] {
+            let mut aot = AotIntcode::new(ic.memory().into_owned(), golden::program);
+            let mut ic = ic.clone();
+            assert_eq!(State::Wait(vec![]), aot.run_input(&[])?);
+            assert_eq!(ic.run_input(&[input])?, aot.run_input(&[input])?);
+            assert_eq!(fallback, aot.is_fallback());
+            assert_eq!(ic.run_input(&[])?, aot.run_input(&[])?);
+        }
+        Ok(())
+    }
+    #[test]
+    fn fallback() -> Result<(), Box<dyn Error>> {
+        // Not the compiled image: the opcode at 0 differs.
+        let mut aot = AotIntcode::new(vec![104, 5, 99], golden::program);
+        assert_eq!(State::Halt(vec![5], 104), aot.run_input(&[])?);
+        assert!(aot.is_fallback());
+
+        // Parameters are not compiled in.
+        let mut aot = AotIntcode::new(
+            Intcode::from_asm(PROGRAM)?.memory().into_owned(),
+            golden::program,
+        );
+        *aot.mem(1) = -1;
+        assert_eq!(
+            Err(IntcodeError::NegativeAddress {
+                pc: 0,
+                instr: 3,
+                addr: -1,
+            }),
+            aot.run_input(&[1])
+        );
+        assert!(!aot.is_fallback());
+        Ok(())
+    }
+    #[test]
+    fn overflow() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_asm(PROGRAM)?;
+        let mut aot = AotIntcode::new(ic.memory().into_owned(), golden::program);
+        ic.base = isize::MAX;
+        aot.m.base = isize::MAX;
+        let overflow = Err(IntcodeError::Overflow { pc: 8 });
+        assert_eq!(overflow, ic.clone().run_input(&[1]));
+        assert_eq!(overflow, aot.run_input(&[1]));
+
+        // The relative base becomes isize::MAX, the mul reads one past it.
+        let mut aot = AotIntcode::new(ic.memory().into_owned(), golden::program);
+        *aot.mem(9) = isize::MAX;
+        *aot.mem(11) = 1;
+        assert_eq!(Err(IntcodeError::Overflow { pc: 10 }), aot.run_input(&[1]));
+        assert!(!aot.is_fallback());
+        Ok(())
+    }
+}
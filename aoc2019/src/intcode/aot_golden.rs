@@ -0,0 +1,139 @@
+// Output of `compile` for `test::PROGRAM`.
+use super::{Exit, IntcodeError, Machine};
+
+pub fn program(
+    m: &mut Machine,
+    input: &mut dyn Iterator<Item = isize>,
+    out: &mut Vec<isize>,
+) -> Result<Exit, IntcodeError> {
+    loop {
+        match m.pc {
+            // in -> [37]
+            0 => {
+                if m.load(0) != 3 {
+                    return Ok(Exit::Fallback);
+                }
+                let a = m.pos(0, 1)?;
+                match input.next() {
+                    Some(v) => m.store(a, v),
+                    None => return Ok(Exit::Wait),
+                }
+                m.pc = 2;
+            }
+            // eq [37], #8 -> [38]
+            2 => {
+                if m.load(2) != 1008 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.pos(2, 1)?) == m.load(4)) as isize;
+                let a = m.pos(2, 3)?;
+                m.store(a, v);
+                m.pc = 6;
+            }
+            // out [38]
+            6 => {
+                if m.load(6) != 4 {
+                    return Ok(Exit::Fallback);
+                }
+                out.push(m.load(m.pos(6, 1)?));
+                m.pc = 8;
+            }
+            // arb #37
+            8 => {
+                if m.load(8) != 109 {
+                    return Ok(Exit::Fallback);
+                }
+                m.base = m.add(8, m.base, m.load(9))?;
+                m.pc = 10;
+            }
+            // mul rel[+0], #2 -> rel[+0]
+            10 => {
+                if m.load(10) != 21202 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.mul(10, m.load(m.rel(10, 1)?), m.load(12))?;
+                let a = m.rel(10, 3)?;
+                m.store(a, v);
+                m.pc = 14;
+            }
+            // add rel[+0], #1 -> rel[+0]
+            14 => {
+                if m.load(14) != 21201 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(14, m.load(m.rel(14, 1)?), m.load(16))?;
+                let a = m.rel(14, 3)?;
+                m.store(a, v);
+                m.pc = 18;
+            }
+            // lt rel[+0], #100 -> [39]
+            18 => {
+                if m.load(18) != 1207 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.rel(18, 1)?) < m.load(20)) as isize;
+                let a = m.pos(18, 3)?;
+                m.store(a, v);
+                m.pc = 22;
+            }
+            // jnz [39], #10
+            22 => {
+                if m.load(22) != 1005 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(m.pos(22, 1)?) != 0 {
+                    m.load(24)
+                } else {
+                    25
+                };
+            }
+            // out rel[+0]
+            25 => {
+                if m.load(25) != 204 {
+                    return Ok(Exit::Fallback);
+                }
+                out.push(m.load(m.rel(25, 1)?));
+                m.pc = 27;
+            }
+            // jz [38], #34
+            27 => {
+                if m.load(27) != 1006 {
+                    return Ok(Exit::Fallback);
+                }
+                m.pc = if m.load(m.pos(27, 1)?) == 0 {
+                    m.load(29)
+                } else {
+                    30
+                };
+            }
+            // add #104, #0 -> [34]
+            30 => {
+                if m.load(30) != 1101 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = m.add(30, m.load(31), m.load(32))?;
+                let a = m.pos(30, 3)?;
+                m.store(a, v);
+                m.pc = 34;
+            }
+            // halt
+            34 => {
+                if m.load(34) != 99 {
+                    return Ok(Exit::Fallback);
+                }
+                return Ok(Exit::Halt);
+            }
+            // lt [99], [0] -> [0]
+            35 => {
+                if m.load(35) != 7 {
+                    return Ok(Exit::Fallback);
+                }
+                let v = (m.load(m.pos(35, 1)?) < m.load(m.pos(35, 2)?)) as isize;
+                let a = m.pos(35, 3)?;
+                m.store(a, v);
+                m.pc = 39;
+            }
+            _ => return Ok(Exit::Fallback),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::{Intcode, IntcodeError, State};
+use std::error::Error;
+use std::io::{BufRead, Write};
+
+/// Decoded output of an ASCII program. `answer` is the last value outside the ASCII range,
+/// the rest of the output is in `text`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AsciiOutput {
+    pub text: String,
+    pub answer: Option<isize>,
+}
+
+impl From<Vec<isize>> for AsciiOutput {
+    fn from(out: Vec<isize>) -> Self {
+        let mut o = AsciiOutput::default();
+        for v in out {
+            if (0..=127).contains(&v) {
+                o.text.push(v as u8 as char);
+            } else {
+                o.answer = Some(v);
+            }
+        }
+        o
+    }
+}
+
+/// Wrapper for programs that read and write lines of ASCII text.
+pub struct AsciiIntcode {
+    pub ic: Intcode,
+}
+
+impl From<Intcode> for AsciiIntcode {
+    fn from(ic: Intcode) -> Self {
+        AsciiIntcode { ic }
+    }
+}
+
+impl AsciiIntcode {
+    /// Feeds `lines`, each terminated by a newline, and runs until the program waits for
+    /// more input or halts.
+    pub fn run(&mut self, lines: &[&str]) -> Result<State<AsciiOutput>, IntcodeError> {
+        let input: Vec<isize> = lines
+            .iter()
+            .flat_map(|l| l.chars().chain(Some('\n')))
+            .map(|c| c as isize)
+            .collect();
+        Ok(self.ic.run_input(&input)?.map(AsciiOutput::from))
+    }
+    /// Passes the program output to `w` and lines read from `r` to the program until it
+    /// halts or `r` is exhausted. Returns the last answer.
+    pub fn interact<R: BufRead, W: Write>(
+        &mut self,
+        mut r: R,
+        mut w: W,
+    ) -> Result<Option<isize>, Box<dyn Error>> {
+        let mut answer = None;
+        let mut state = self.run(&[])?;
+        loop {
+            let out = state.out();
+            write!(w, "{}", out.text)?;
+            w.flush()?;
+            answer = out.answer.or(answer);
+            if state.is_halt() {
+                return Ok(answer);
+            }
+            let mut line = String::new();
+            if r.read_line(&mut line)? == 0 {
+                return Ok(answer);
+            }
+            state = self.run(&[line.trim_end_matches(['\r', '\n'])])?;
+        }
+    }
+    /// `interact` on stdin and stdout.
+    pub fn interactive(&mut self) -> Result<Option<isize>, Box<dyn Error>> {
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+        self.interact(stdin.lock(), stdout.lock())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Echoes every line in upper case, halts with a final 1000 after "q".
+    const SHOUT: &str = "
+            out #62
+        loop:
+            in -> [c]
+            eq [c], #113 -> [t]
+            jnz [t], #quit
+            lt [c], #97 -> [t]
+            jnz [t], #print
+            add [c], #-32 -> [c]
+        print:
+            out [c]
+            eq [c], #10 -> [t]
+            jz [t], #loop
+            out #62
+            jnz #1, #loop
+        quit:
+            out #1000
+            halt
+        c: data 0
+        t: data 0
+    ";
+
+    #[test]
+    fn lines() -> Result<(), Box<dyn Error>> {
+        let mut ic = AsciiIntcode::from(Intcode::from_asm(SHOUT)?);
+        let out = ic.run(&["hello", "Abc"])?;
+        assert_eq!(
+            State::Wait(AsciiOutput {
+                text: ">HELLO\n>ABC\n>".to_string(),
+                answer: None,
+            }),
+            out
+        );
+        let out = ic.run(&["q"])?;
+        assert!(out.is_halt());
+        assert_eq!(Some(1000), out.out().answer);
+        assert_eq!("", out.out().text);
+        Ok(())
+    }
+    #[test]
+    fn interact() -> Result<(), Box<dyn Error>> {
+        let mut ic = AsciiIntcode::from(Intcode::from_asm(SHOUT)?);
+        let mut out = Vec::new();
+        assert_eq!(Some(1000), ic.interact(&b"go\r\nnorth\nq\n"[..], &mut out)?);
+        assert_eq!(">GO\n>NORTH\n>", String::from_utf8(out)?);
+
+        let mut ic = AsciiIntcode::from(Intcode::from_asm(SHOUT)?);
+        assert_eq!(None, ic.interact(&b"x"[..], &mut Vec::new())?);
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use super::{Instr, Intcode, Mode, Opcode};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl Error for AsmError {}
+
+fn err<T>(line: usize, msg: String) -> Result<T, AsmError> {
+    Err(AsmError { line, msg })
+}
+
+enum Value {
+    Int(isize),
+    Label(String, isize),
+}
+
+pub(super) fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_value(line: usize, s: &str) -> Result<Value, AsmError> {
+    let s = s.trim();
+    if let Ok(v) = s.parse() {
+        return Ok(Value::Int(v));
+    }
+    let (label, off) = match s.find(['+', '-']) {
+        Some(i) => match s[i..].replace(' ', "").parse() {
+            Ok(off) => (s[..i].trim(), off),
+            Err(_) => return err(line, format!("invalid offset in `{}`", s)),
+        },
+        None => (s, 0),
+    };
+    if !is_ident(label) {
+        return err(line, format!("invalid value `{}`", s));
+    }
+    Ok(Value::Label(label.to_string(), off))
+}
+
+fn parse_operand(line: usize, s: &str) -> Result<(Mode, Value), AsmError> {
+    let s = s.trim();
+    if let Some(v) = s.strip_prefix('#') {
+        return Ok((Mode::Immediate, parse_value(line, v)?));
+    }
+    if let Some(v) = s.strip_prefix("rel[").and_then(|v| v.strip_suffix(']')) {
+        return Ok((Mode::Relative, parse_value(line, v)?));
+    }
+    if let Some(v) = s.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return Ok((Mode::Position, parse_value(line, v)?));
+    }
+    err(line, format!("invalid operand `{}`", s))
+}
+
+fn split_list(s: &str) -> Vec<&str> {
+    if s.trim().is_empty() {
+        Vec::new()
+    } else {
+        s.split(',').collect()
+    }
+}
+
+fn parse_instr(line: usize, op: Opcode, args: &str) -> Result<Vec<Value>, AsmError> {
+    let (srcs, dst) = match args.split_once("->") {
+        Some((srcs, dst)) => (split_list(srcs), Some(dst)),
+        None => (split_list(args), None),
+    };
+    if dst.is_some() != op.writes() {
+        let msg = if op.writes() {
+            format!("`{}` needs a destination operand", op.mnemonic())
+        } else {
+            format!("`{}` takes no destination operand", op.mnemonic())
+        };
+        return err(line, msg);
+    }
+    let nsrcs = op.params() - op.writes() as usize;
+    if srcs.len() != nsrcs {
+        return err(
+            line,
+            format!(
+                "`{}` takes {} source operand(s), got {}",
+                op.mnemonic(),
+                nsrcs,
+                srcs.len()
+            ),
+        );
+    }
+
+    let mut modes = [Mode::Position; 3];
+    let mut words = vec![Value::Int(0)];
+    for (i, arg) in srcs.into_iter().chain(dst).enumerate() {
+        let (mode, value) = parse_operand(line, arg)?;
+        modes[i] = mode;
+        words.push(value);
+    }
+    words[0] = Value::Int(Instr { op, modes }.encode());
+    Ok(words)
+}
+
+/// Assembles `src` into a memory image.
+///
+/// Every line holds an optional `label:`, followed by either an instruction in the syntax the
+/// disassembler prints (`add [12], #5 -> rel[+3]`) or a `data` directive with a comma separated
+/// list of values. Values are integers or labels with an optional offset (`buf+2`). Everything
+/// after a `;` is a comment.
+pub fn assemble_mem(src: &str) -> Result<Vec<isize>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut words = Vec::new();
+
+    for (idx, text) in src.lines().enumerate() {
+        let line = idx + 1;
+        let mut text = text.split(';').next().unwrap().trim();
+
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_ident(label) {
+                break;
+            }
+            if labels.insert(label.to_string(), words.len()).is_some() {
+                return err(line, format!("duplicate label `{}`", label));
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let values = if mnemonic == "data" {
+            let values = split_list(args)
+                .into_iter()
+                .map(|v| parse_value(line, v))
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.is_empty() {
+                return err(line, "`data` needs at least one value".to_string());
+            }
+            values
+        } else {
+            match Opcode::ALL.iter().find(|op| op.mnemonic() == mnemonic) {
+                Some(&op) => parse_instr(line, op, args)?,
+                None => return err(line, format!("unknown mnemonic `{}`", mnemonic)),
+            }
+        };
+        words.extend(values.into_iter().map(|v| (line, v)));
+    }
+
+    words
+        .into_iter()
+        .map(|(line, v)| match v {
+            Value::Int(v) => Ok(v),
+            Value::Label(label, off) => match labels.get(&label) {
+                Some(&addr) => Ok(addr as isize + off),
+                None => err(line, format!("undefined label `{}`", label)),
+            },
+        })
+        .collect()
+}
+
+/// Assembles `src` into the comma separated form `Intcode::from_str` consumes.
+pub fn assemble(src: &str) -> Result<String, AsmError> {
+    Ok(assemble_mem(src)?
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+impl Intcode {
+    pub fn from_asm(src: &str) -> Result<Self, Box<dyn Error>> {
+        assemble(src)?.parse()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn labels() -> Result<(), Box<dyn Error>> {
+        let src = "
+            ; is the input equal to 8?
+            start:  in -> [x]
+                    eq [x], [eight] -> [x]
+                    out [x]
+                    halt
+            x:      data -1
+            eight:  data 8
+        ";
+        assert_eq!("3,9,8,9,10,9,4,9,99,-1,8", assemble(src)?);
+        assert_eq!(1, Intcode::from_asm(src)?.run_input(&[8])?.out()[0]);
+        assert_eq!(
+            "109,11,21101,1,2,-1,204,-1,1105,1,14,5,8,9",
+            assemble(
+                "arb #buf
+                 add #1, #2 -> rel[-1]
+                 out rel[-1]
+                 loop: jnz #1, #start
+                 buf: data 5, loop, buf-2
+                 start:"
+            )?
+        );
+        Ok(())
+    }
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn Error>> {
+        for src in &[
+            "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+            "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+            "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+        ] {
+            let listing = Intcode::from_str(src)?
+                .disassemble()
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert_eq!(*src, assemble(&listing)?);
+        }
+        Ok(())
+    }
+    #[test]
+    fn errors() {
+        let e = |src| assemble(src).unwrap_err().to_string();
+        assert_eq!("line 2: unknown mnemonic `nop`", e("halt\nnop"));
+        assert_eq!("line 1: `add` needs a destination operand", e("add #1, #2"));
+        assert_eq!(
+            "line 1: `out` takes 1 source operand(s), got 2",
+            e("out #1, #2")
+        );
+        assert_eq!(
+            "line 1: `jz` takes no destination operand",
+            e("jz #1 -> [2]")
+        );
+        assert_eq!("line 1: invalid operand `1`", e("out 1"));
+        assert_eq!("line 3: undefined label `end`", e("\n\njz #0, #end"));
+        assert_eq!("line 2: duplicate label `a`", e("a: halt\na: halt"));
+        assert_eq!("line 1: invalid value `1x`", e("data 1x"));
+    }
+}
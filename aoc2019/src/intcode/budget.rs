@@ -0,0 +1,112 @@
+use super::wide::Cell;
+use super::Intcode;
+use std::time::{Duration, Instant};
+
+// The clock is only read every few instructions, it is much slower than executing one.
+const CLOCK_INTERVAL: u32 = 1024;
+
+#[derive(Clone, Default)]
+pub(super) struct Budget {
+    steps: Option<u64>,
+    deadline: Option<Instant>,
+    tick: u32,
+    // Once the deadline passed every run stops right away, until a new one is set.
+    expired: bool,
+}
+
+impl Budget {
+    /// Makes the next `exhausted` read the clock, called at the start of every run.
+    pub(super) fn start(&mut self) {
+        self.tick = 0;
+    }
+    pub(super) fn exhausted(&mut self) -> bool {
+        if self.expired || self.steps == Some(0) {
+            return true;
+        }
+        if let Some(deadline) = self.deadline {
+            self.tick = self.tick.wrapping_add(1);
+            if self.tick % CLOCK_INTERVAL == 1 && Instant::now() >= deadline {
+                self.expired = true;
+            }
+        }
+        self.expired
+    }
+    pub(super) fn charge(&mut self) {
+        if let Some(steps) = &mut self.steps {
+            *steps -= 1;
+        }
+    }
+    pub(super) fn steps(&self) -> Option<u64> {
+        self.steps
+    }
+    pub(super) fn set_steps(&mut self, steps: Option<u64>) {
+        self.steps = steps;
+    }
+    pub(super) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+        self.tick = 0;
+        self.expired = false;
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Limits the number of instructions `run`, `run_with` and `run_input` may execute from
+    /// now on, `None` removes the limit.
+    pub fn set_budget(&mut self, steps: Option<u64>) {
+        self.budget.set_steps(steps);
+    }
+    /// Instructions left before the budget is exhausted.
+    pub fn budget(&self) -> Option<u64> {
+        self.budget.steps()
+    }
+    /// Stops `run`, `run_with` and `run_input` once `deadline` passed, including any later
+    /// call until the deadline is changed.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.budget.set_deadline(deadline);
+    }
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.set_deadline(Some(Instant::now() + timeout));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{IntcodeError, State};
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const SPIN: &str = "104,1,1105,1,0";
+
+    #[test]
+    fn steps() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str(SPIN)?;
+        ic.set_budget(Some(5));
+        assert_eq!(State::Budget(vec![1, 1, 1], 2), ic.run_input(&[])?);
+        assert_eq!(Some(0), ic.budget());
+        assert_eq!(State::Budget(vec![], 2), ic.run_input(&[])?);
+        ic.set_budget(Some(1));
+        assert_eq!(Err(IntcodeError::BudgetExhausted { pc: 0 }), ic.run());
+
+        let mut ic = Intcode::from_str("3,0,99")?;
+        ic.set_budget(Some(1));
+        assert_eq!(State::Wait(vec![]), ic.run_input(&[])?);
+        assert_eq!(Some(1), ic.budget());
+        assert_eq!(State::Budget(vec![], 2), ic.run_input(&[5])?);
+        Ok(())
+    }
+    #[test]
+    fn deadline() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str(SPIN)?;
+        let start = Instant::now();
+        ic.set_timeout(Duration::from_millis(10));
+        assert!(matches!(ic.run_input(&[])?, State::Budget(_, _)));
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(State::Budget(vec![], ic.pc()), ic.run_input(&[])?);
+
+        ic.set_deadline(None);
+        ic.set_budget(Some(2));
+        assert_eq!(&vec![1], ic.run_input(&[])?.out());
+        Ok(())
+    }
+}
@@ -0,0 +1,455 @@
+use super::disasm::{decode_at, Line};
+use super::{Intcode, Mode, Opcode, Operand};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// How control leaves a basic block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Runs into the block at the next address.
+    Fall(usize),
+    Jump(usize),
+    Branch {
+        taken: usize,
+        fall: usize,
+    },
+    /// A jump to `target` after a constant `ret` was pushed through the relative base.
+    Call {
+        target: usize,
+        ret: usize,
+    },
+    /// A call through a position mode operand.
+    IndirectCall {
+        ret: usize,
+    },
+    /// A jump through a relative mode operand. Conditional ones also `fall` through.
+    Return {
+        fall: Option<usize>,
+    },
+    /// A jump through a position mode operand.
+    Indirect {
+        fall: Option<usize>,
+    },
+    Halt,
+    /// No complete instruction at this address.
+    Invalid(usize),
+}
+
+impl Flow {
+    /// Addresses the block may continue at, as far as they are known.
+    pub fn successors(&self) -> Vec<usize> {
+        match *self {
+            Flow::Fall(next) | Flow::Jump(next) => vec![next],
+            Flow::Branch { taken, fall } => vec![taken, fall],
+            Flow::Call { target, ret } => vec![target, ret],
+            Flow::IndirectCall { ret } => vec![ret],
+            Flow::Return { fall } | Flow::Indirect { fall } => fall.into_iter().collect(),
+            Flow::Halt | Flow::Invalid(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: usize,
+    pub instrs: Vec<Line>,
+    pub flow: Flow,
+}
+
+/// Basic blocks reachable from address 0, found by following jumps with immediate
+/// targets, and the entry points of the functions called from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cfg {
+    pub blocks: BTreeMap<usize, Block>,
+    pub functions: BTreeSet<usize>,
+}
+
+fn constant(op: Opcode, args: &[Operand]) -> Option<isize> {
+    match (args[0].mode, args[1].mode) {
+        (Mode::Immediate, Mode::Immediate) => match op {
+            Opcode::Add => args[0].value.checked_add(args[1].value),
+            Opcode::Mul => args[0].value.checked_mul(args[1].value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn target(arg: &Operand) -> Option<usize> {
+    match arg.mode {
+        Mode::Immediate if arg.value >= 0 => Some(arg.value as usize),
+        _ => None,
+    }
+}
+
+/// Decodes from `start` up to the first jump or halt, or up to the next of the `leaders`.
+/// Jump operands in `patched` cells are not taken as constants.
+fn walk(
+    mem: &[isize],
+    start: usize,
+    leaders: Option<&BTreeSet<usize>>,
+    patched: &BTreeSet<usize>,
+) -> (Vec<Line>, Flow) {
+    let mut instrs = Vec::new();
+    // Constants stored through the relative base, candidates for return addresses.
+    let mut pushed = Vec::new();
+    let mut addr = start;
+    loop {
+        if addr != start && leaders.is_some_and(|l| l.contains(&addr)) {
+            return (instrs, Flow::Fall(addr));
+        }
+        let line = match decode_at(mem, addr) {
+            Some(line) => line,
+            None => return (instrs, Flow::Invalid(addr)),
+        };
+        let next = addr + line.len();
+        let flow = match &line {
+            Line::Instr { op, args, .. } => match op {
+                Opcode::Add | Opcode::Mul if args[2].mode == Mode::Relative => {
+                    pushed.extend(constant(*op, args));
+                    None
+                }
+                Opcode::Jnz | Opcode::Jz => {
+                    let patched = |i: usize| patched.contains(&(addr + i));
+                    let always = match args[0].mode {
+                        Mode::Immediate if !patched(1) => {
+                            Some((args[0].value != 0) == (*op == Opcode::Jnz))
+                        }
+                        _ => None,
+                    };
+                    let fall = if always == Some(true) {
+                        None
+                    } else {
+                        Some(next)
+                    };
+                    let indirect = args[1].mode == Mode::Position || patched(2);
+                    let called = pushed.contains(&(next as isize));
+                    Some(match (always, target(&args[1]).filter(|_| !patched(2))) {
+                        (Some(false), _) => Flow::Fall(next),
+                        (Some(true), Some(t)) if called => Flow::Call {
+                            target: t,
+                            ret: next,
+                        },
+                        (Some(true), Some(t)) => Flow::Jump(t),
+                        (None, Some(t)) => Flow::Branch {
+                            taken: t,
+                            fall: next,
+                        },
+                        (Some(true), None) if indirect && called => {
+                            Flow::IndirectCall { ret: next }
+                        }
+                        (_, None) if args[1].mode == Mode::Relative => Flow::Return { fall },
+                        (_, None) => Flow::Indirect { fall },
+                    })
+                }
+                Opcode::Halt => Some(Flow::Halt),
+                _ => None,
+            },
+            Line::Data { .. } => unreachable!(),
+        };
+        instrs.push(line);
+        match flow {
+            // A jump that is never taken does not end the block.
+            Some(Flow::Fall(_)) | None => addr = next,
+            Some(flow) => return (instrs, flow),
+        }
+    }
+}
+
+type Walks = BTreeMap<usize, (Vec<Line>, Flow)>;
+
+/// The cell `line` writes to, if its address is a constant.
+fn write_dest(line: &Line) -> Option<usize> {
+    match line {
+        Line::Instr { op, args, .. } if op.writes() => {
+            let dest = &args[op.params() - 1];
+            match dest.mode {
+                Mode::Position if dest.value >= 0 => Some(dest.value as usize),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Operand cells of jumps that are written by an instruction which can run before the
+/// jump. Cells only written once the jump is behind for good are still constants.
+fn patched_operands(walks: &Walks) -> BTreeSet<usize> {
+    let mut jumps = BTreeMap::new();
+    for (&start, (instrs, _)) in walks {
+        for line in instrs {
+            if let Line::Instr {
+                addr,
+                op: Opcode::Jnz | Opcode::Jz,
+                ..
+            } = *line
+            {
+                for cell in addr + 1..addr + 3 {
+                    jumps
+                        .entry(cell)
+                        .or_insert_with(Vec::new)
+                        .push((start, addr));
+                }
+            }
+        }
+    }
+    let reach = |from: usize| {
+        let mut seen = BTreeSet::new();
+        let mut todo = walks[&from].1.successors();
+        while let Some(start) = todo.pop() {
+            if walks.contains_key(&start) && seen.insert(start) {
+                todo.extend(walks[&start].1.successors());
+            }
+        }
+        seen
+    };
+    let mut patched = BTreeSet::new();
+    for (&from, (instrs, _)) in walks {
+        let mut reached = None;
+        for line in instrs {
+            let cell = match write_dest(line) {
+                Some(cell) => cell,
+                None => continue,
+            };
+            for &(start, jump) in jumps.get(&cell).into_iter().flatten() {
+                if (start == from && line.addr() < jump)
+                    || reached.get_or_insert_with(|| reach(from)).contains(&start)
+                {
+                    patched.insert(cell);
+                }
+            }
+        }
+    }
+    patched
+}
+
+impl Cfg {
+    pub fn new(mem: &[isize]) -> Self {
+        // Exploring again whenever more jumps turn out to be patched.
+        let mut patched = BTreeSet::new();
+        loop {
+            let (walks, functions) = Cfg::explore(mem, &patched);
+            let found = patched_operands(&walks);
+            if found.is_subset(&patched) {
+                // A leader may split a call from its pushed return address, so calls are
+                // taken from the walks that saw both.
+                let calls: BTreeMap<_, _> = walks
+                    .values()
+                    .filter(|(_, flow)| {
+                        matches!(flow, Flow::Call { .. } | Flow::IndirectCall { .. })
+                    })
+                    .filter_map(|(instrs, flow)| Some((instrs.last()?.addr(), *flow)))
+                    .collect();
+                let leaders: BTreeSet<_> = walks.keys().cloned().collect();
+                let blocks = leaders
+                    .iter()
+                    .map(|&start| {
+                        let (instrs, flow) = walk(mem, start, Some(&leaders), &patched);
+                        let flow = match instrs.last().and_then(|l| calls.get(&l.addr())) {
+                            Some(&call) => call,
+                            None => flow,
+                        };
+                        (
+                            start,
+                            Block {
+                                start,
+                                instrs,
+                                flow,
+                            },
+                        )
+                    })
+                    .collect();
+                return Cfg { blocks, functions };
+            }
+            patched.extend(found);
+        }
+    }
+    /// Walks from every block leader reachable from 0, and the function entries.
+    fn explore(mem: &[isize], patched: &BTreeSet<usize>) -> (Walks, BTreeSet<usize>) {
+        let mut walks = BTreeMap::new();
+        let mut functions = BTreeSet::new();
+        let mut todo = vec![0];
+        functions.insert(0);
+        while let Some(start) = todo.pop() {
+            if walks.contains_key(&start) {
+                continue;
+            }
+            let (instrs, flow) = walk(mem, start, None, patched);
+            if let Flow::Call { target, .. } = flow {
+                functions.insert(target);
+            }
+            todo.extend(flow.successors());
+            walks.insert(start, (instrs, flow));
+        }
+        (walks, functions)
+    }
+    /// Graphviz source with one node per block. Calls are bold, return sites dashed and
+    /// function entries drawn with a double border.
+    pub fn to_dot(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "digraph intcode {{").unwrap();
+        writeln!(s, "    node [shape=box, fontname=monospace];").unwrap();
+        for (start, block) in &self.blocks {
+            let mut label = String::new();
+            for line in &block.instrs {
+                write!(label, "{}: {}\\l", line.addr(), line).unwrap();
+            }
+            if let Flow::Invalid(addr) = block.flow {
+                write!(label, "{}: invalid\\l", addr).unwrap();
+            }
+            let entry = if self.functions.contains(start) {
+                ", peripheries=2"
+            } else {
+                ""
+            };
+            writeln!(s, "    b{} [label=\"{}\"{}];", start, label, entry).unwrap();
+        }
+        for (start, block) in &self.blocks {
+            let mut edge = |to: usize, attrs: &str| {
+                writeln!(s, "    b{} -> b{}{};", start, to, attrs).unwrap();
+            };
+            match block.flow {
+                Flow::Fall(to) | Flow::Jump(to) => edge(to, ""),
+                Flow::Branch { taken, fall } => {
+                    edge(taken, " [label=\"taken\"]");
+                    edge(fall, "");
+                }
+                Flow::Call { target, ret } => {
+                    edge(target, " [label=\"call\", style=bold]");
+                    edge(ret, " [style=dashed]");
+                }
+                Flow::IndirectCall { ret } => edge(ret, " [style=dashed]"),
+                Flow::Return { fall: Some(to) } | Flow::Indirect { fall: Some(to) } => edge(to, ""),
+                _ => (),
+            }
+        }
+        writeln!(s, "}}").unwrap();
+        s
+    }
+}
+
+impl Intcode {
+    pub fn cfg(&self) -> Cfg {
+        Cfg::new(&self.image())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn call() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_asm(
+            "
+                    arb #stack
+                    in -> [x]
+            loop:   add #back, #0 -> rel[0]
+                    jz #0, #double
+            back:   lt [x], #100 -> [c]
+                    jnz [c], #loop
+                    out [x]
+                    halt
+            double: mul [x], #2 -> [x]
+                    jnz #1, rel[0]
+            x:      data 0
+            c:      data 0
+            stack:  data 0
+            ",
+        )?;
+        let cfg = ic.cfg();
+        let flows: Vec<_> = cfg.blocks.values().map(|b| (b.start, b.flow)).collect();
+        assert_eq!(
+            vec![
+                (0, Flow::Fall(4)),
+                (
+                    4,
+                    Flow::Call {
+                        target: 21,
+                        ret: 11
+                    }
+                ),
+                (11, Flow::Branch { taken: 4, fall: 18 }),
+                (18, Flow::Halt),
+                (21, Flow::Return { fall: None }),
+            ],
+            flows
+        );
+        assert_eq!(
+            vec![0, 21],
+            cfg.functions.iter().cloned().collect::<Vec<_>>()
+        );
+        assert_eq!(2, cfg.blocks[&18].instrs.len());
+
+        let dot = cfg.to_dot();
+        assert!(dot.starts_with("digraph intcode {\n"));
+        assert!(dot.contains("    b21 [label=\"21: mul [28], #2 -> [28]\\l25: jnz #1, rel[+0]\\l\", peripheries=2];\n"));
+        assert!(dot.contains(
+            "    b4 -> b21 [label=\"call\", style=bold];\n    b4 -> b11 [style=dashed];\n"
+        ));
+        assert!(dot.contains("    b11 -> b4 [label=\"taken\"];\n"));
+        Ok(())
+    }
+    #[test]
+    fn split_call() -> Result<(), Box<dyn Error>> {
+        // The loop branches back between pushing the return address and the call.
+        let ic = Intcode::from_asm(
+            "
+                    arb #stack
+                    in -> [x]
+                    add #back, #0 -> rel[0]
+            arg:    add [x], #0 -> rel[1]
+                    jz #0, #dec
+            back:   jnz [x], #arg
+                    halt
+            dec:    add rel[1], #-1 -> [x]
+                    jnz #1, rel[0]
+            x:      data 0
+            stack:  data 0
+            ",
+        )?;
+        let cfg = ic.cfg();
+        assert_eq!(Flow::Fall(8), cfg.blocks[&0].flow);
+        assert_eq!(
+            Flow::Call {
+                target: 19,
+                ret: 15
+            },
+            cfg.blocks[&8].flow
+        );
+        assert_eq!(
+            vec![0, 19],
+            cfg.functions.iter().cloned().collect::<Vec<_>>()
+        );
+        assert!(cfg
+            .to_dot()
+            .contains("    b8 -> b19 [label=\"call\", style=bold];\n"));
+        Ok(())
+    }
+    #[test]
+    fn flows() {
+        // A jump that is never taken, then one through a position mode operand.
+        let cfg = Cfg::new(&[1106, 1, 7, 5, 9, 10, 99, 0, 0, 1, 6]);
+        assert_eq!(2, cfg.blocks[&0].instrs.len());
+        assert_eq!(Flow::Indirect { fall: Some(6) }, cfg.blocks[&0].flow);
+        assert_eq!(Flow::Halt, cfg.blocks[&6].flow);
+
+        let cfg = Cfg::new(&[1105, 1, 4, 99, 42]);
+        assert_eq!(Flow::Jump(4), cfg.blocks[&0].flow);
+        assert_eq!(Flow::Invalid(4), cfg.blocks[&4].flow);
+        assert!(cfg.to_dot().contains("    b4 [label=\"4: invalid\\l\"];\n"));
+
+        // Pushes 7 and calls the function at [9].
+        let cfg = Cfg::new(&[21101, 7, 0, 0, 105, 1, 9, 99, 99, 8]);
+        assert_eq!(Flow::IndirectCall { ret: 7 }, cfg.blocks[&0].flow);
+        assert_eq!(Flow::Halt, cfg.blocks[&7].flow);
+
+        // Patches the target of its own call, the way day 19 passes function pointers.
+        let cfg = Cfg::new(&[
+            1101, 0, 15, 10, 21101, 11, 0, 0, 1105, 1, 0, 99, 0, 0, 0, 99,
+        ]);
+        assert_eq!(Flow::IndirectCall { ret: 11 }, cfg.blocks[&0].flow);
+        assert_eq!(vec![0], cfg.functions.iter().cloned().collect::<Vec<_>>());
+    }
+}
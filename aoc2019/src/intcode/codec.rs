@@ -0,0 +1,130 @@
+use std::io::{self, Read, Write};
+
+// LEB128 varints, signed values are zigzag encoded first.
+
+pub fn write_uvar<W: Write>(w: &mut W, mut v: u64) -> io::Result<()> {
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub fn write_var<W: Write>(w: &mut W, v: isize) -> io::Result<()> {
+    let v = v as i64;
+    write_uvar(w, ((v << 1) ^ (v >> 63)) as u64)
+}
+
+pub fn read_uvar<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut v = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        v |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+pub fn read_var<R: Read>(r: &mut R) -> io::Result<isize> {
+    let v = read_uvar(r)?;
+    Ok(((v >> 1) as i64 ^ -((v & 1) as i64)) as isize)
+}
+
+// Same encoding for values beyond 64 bits, identical to `write_var` for the rest.
+pub fn write_var128<W: Write>(w: &mut W, v: i128) -> io::Result<()> {
+    let mut v = ((v << 1) ^ (v >> 127)) as u128;
+    loop {
+        let byte = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+pub fn read_var128<R: Read>(r: &mut R) -> io::Result<i128> {
+    let mut v = 0;
+    for shift in (0..128).step_by(7) {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        v |= u128::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok((v >> 1) as i128 ^ -((v & 1) as i128));
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+pub fn read_usize<R: Read>(r: &mut R) -> io::Result<usize> {
+    let v = read_uvar(r)?;
+    if v > usize::MAX as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "value too large",
+        ));
+    }
+    Ok(v as usize)
+}
+
+pub fn read_header<R: Read>(r: &mut R, magic: &[u8; 4], version: u8) -> io::Result<()> {
+    let mut header = [0; 5];
+    r.read_exact(&mut header)?;
+    if &header[..4] != magic {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "bad magic"));
+    }
+    if header[4] != version {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported version {}", header[4]),
+        ));
+    }
+    Ok(())
+}
+
+pub fn write_header<W: Write>(w: &mut W, magic: &[u8; 4], version: u8) -> io::Result<()> {
+    w.write_all(magic)?;
+    w.write_all(&[version])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn varint() -> io::Result<()> {
+        let values = [0, 1, -1, 63, -64, 64, 1 << 40, isize::MIN, isize::MAX];
+        let mut buf = Vec::new();
+        for &v in &values {
+            write_var(&mut buf, v)?;
+        }
+        assert_eq!(&buf[..5], &[0, 2, 1, 126, 127]);
+        let mut r = &buf[..];
+        for &v in &values {
+            assert_eq!(v, read_var(&mut r)?);
+        }
+        assert!(r.is_empty());
+
+        let mut wide = Vec::new();
+        for &v in &values {
+            write_var128(&mut wide, v as i128)?;
+        }
+        assert_eq!(buf, wide);
+        write_var128(&mut wide, i128::MIN)?;
+        let mut r = &wide[buf.len()..];
+        assert_eq!(i128::MIN, read_var128(&mut r)?);
+        Ok(())
+    }
+}
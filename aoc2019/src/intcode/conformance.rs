@@ -0,0 +1,406 @@
+// Behaviour every backend has to share. Each case runs on every backend in `backends`,
+// one `run_input` call per entry of `runs`, and the listed cells are compared afterwards.
+// Compiled programs (`aot`) can not be built at test time and are checked in `aot.rs`.
+
+use super::fast::FastIntcode;
+use super::{Cell, Intcode, IntcodeError, Paged, State, WatchHit};
+use num_bigint::BigInt;
+use std::str::FromStr;
+
+type Run = Result<State<Vec<isize>>, IntcodeError>;
+
+trait Backend {
+    fn run(&mut self, input: &[isize]) -> Run;
+    fn peek(&mut self, addr: usize) -> isize;
+}
+
+impl Backend for FastIntcode {
+    fn run(&mut self, input: &[isize]) -> Run {
+        self.run_input(input)
+    }
+    fn peek(&mut self, addr: usize) -> isize {
+        self.memory().get(addr).cloned().unwrap_or(0)
+    }
+}
+
+impl<C: Cell> Backend for Intcode<C> {
+    fn run(&mut self, input: &[isize]) -> Run {
+        let input: Vec<_> = input.iter().map(|&v| C::from_isize(v)).collect();
+        let small = |v: &C| v.to_isize().unwrap();
+        let out = |v: &[C]| v.iter().map(small).collect();
+        Ok(match self.run_input(&input)? {
+            State::Wait(o) => State::Wait(out(&o)),
+            State::Halt(o, code) => State::Halt(out(&o), small(&code)),
+            State::Watch(o, hit) => State::Watch(
+                out(&o),
+                WatchHit {
+                    pc: hit.pc,
+                    addr: hit.addr,
+                    access: hit.access,
+                    old: small(&hit.old),
+                    new: small(&hit.new),
+                },
+            ),
+            State::Budget(o, pc) => State::Budget(out(&o), pc),
+        })
+    }
+    fn peek(&mut self, addr: usize) -> isize {
+        Intcode::peek(self, addr).to_isize().unwrap()
+    }
+}
+
+fn backends(code: &str) -> Vec<(&'static str, Box<dyn Backend>)> {
+    let ic = Intcode::from_str(code).unwrap();
+    vec![
+        ("dense", Box::new(ic.clone())),
+        (
+            "paged",
+            Box::new(Intcode::with_memory(Paged::from(ic.memory().into_owned()))),
+        ),
+        ("fork", Box::new(ic.fork())),
+        ("fast", Box::new(ic.to_fast())),
+        ("i64", Box::new(ic.to_wide::<i64>())),
+        ("i128", Box::new(ic.to_wide::<i128>())),
+        ("bigint", Box::new(ic.to_wide::<BigInt>())),
+    ]
+}
+
+struct Case {
+    name: &'static str,
+    code: &'static str,
+    runs: &'static [(&'static [isize], Expect)],
+    mem: &'static [(usize, isize)],
+}
+
+enum Expect {
+    Wait(&'static [isize]),
+    Halt(&'static [isize], isize),
+    Fail(IntcodeError),
+}
+
+impl Expect {
+    fn run(&self) -> Run {
+        match self {
+            Expect::Wait(out) => Ok(State::Wait(out.to_vec())),
+            Expect::Halt(out, code) => Ok(State::Halt(out.to_vec(), *code)),
+            Expect::Fail(e) => Err(*e),
+        }
+    }
+}
+
+use Expect::*;
+use IntcodeError::*;
+
+const CASES: &[Case] = &[
+    // Arithmetic, one parameter mode at a time.
+    Case {
+        name: "add position",
+        code: "1,5,6,0,99,30,12",
+        runs: &[(&[], Halt(&[], 42))],
+        mem: &[(0, 42)],
+    },
+    Case {
+        name: "add immediate",
+        code: "1101,30,12,0,99",
+        runs: &[(&[], Halt(&[], 42))],
+        mem: &[(0, 42)],
+    },
+    Case {
+        name: "add relative",
+        code: "109,10,22201,0,1,2,99,0,0,0,30,12,0",
+        runs: &[(&[], Halt(&[], 109))],
+        mem: &[(12, 42)],
+    },
+    Case {
+        name: "mul position and immediate",
+        code: "1002,4,3,4,33",
+        runs: &[(&[], Halt(&[], 1002))],
+        mem: &[(4, 99)],
+    },
+    Case {
+        name: "mul relative write",
+        code: "109,7,21102,6,7,0,99,0",
+        runs: &[(&[], Halt(&[], 109))],
+        mem: &[(7, 42)],
+    },
+    // Input and output.
+    Case {
+        name: "input position and relative",
+        code: "3,11,109,12,203,0,4,11,204,0,99,0,0",
+        runs: &[(&[5, 6], Halt(&[5, 6], 3))],
+        mem: &[(11, 5), (12, 6)],
+    },
+    Case {
+        name: "input immediate writes the parameter cell",
+        code: "103,0,4,1,99",
+        runs: &[(&[7], Halt(&[7], 103))],
+        mem: &[(1, 7)],
+    },
+    Case {
+        name: "output modes",
+        code: "104,7,4,0,109,1,204,-1,99",
+        runs: &[(&[], Halt(&[7, 104, 104], 104))],
+        mem: &[],
+    },
+    Case {
+        name: "unused mode digits are ignored",
+        code: "10104,5,99",
+        runs: &[(&[], Halt(&[5], 10104))],
+        mem: &[],
+    },
+    // Jumps.
+    Case {
+        name: "jumps position",
+        code: "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+        runs: &[(&[0], Halt(&[0], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "jumps position taken",
+        code: "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+        runs: &[(&[5], Halt(&[1], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "jumps immediate",
+        code: "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+        runs: &[(&[0], Halt(&[0], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "jumps immediate taken",
+        code: "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+        runs: &[(&[5], Halt(&[1], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "jnz relative",
+        code: "109,20,2205,0,1,104,0,99,104,1,99,0,0,0,0,0,0,0,0,0,1,8",
+        runs: &[(&[], Halt(&[1], 109))],
+        mem: &[],
+    },
+    Case {
+        name: "jz relative",
+        code: "109,20,2206,0,1,104,0,99,104,1,99,0,0,0,0,0,0,0,0,0,1,8",
+        runs: &[(&[], Halt(&[0], 109))],
+        mem: &[],
+    },
+    // Comparisons.
+    Case {
+        name: "eq position",
+        code: "3,9,8,9,10,9,4,9,99,-1,8",
+        runs: &[(&[8], Halt(&[1], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "lt position",
+        code: "3,9,7,9,10,9,4,9,99,-1,8",
+        runs: &[(&[8], Halt(&[0], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "eq immediate",
+        code: "3,3,1108,-1,8,3,4,3,99",
+        runs: &[(&[7], Halt(&[0], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "lt immediate",
+        code: "3,3,1107,-1,8,3,4,3,99",
+        runs: &[(&[7], Halt(&[1], 3))],
+        mem: &[],
+    },
+    Case {
+        name: "lt relative",
+        code: "109,10,22207,0,1,2,204,2,99,0,3,4,0",
+        runs: &[(&[], Halt(&[1], 109))],
+        mem: &[],
+    },
+    Case {
+        name: "eq relative",
+        code: "109,10,22208,0,1,2,204,2,99,0,3,4,0",
+        runs: &[(&[], Halt(&[0], 109))],
+        mem: &[],
+    },
+    // Relative base.
+    Case {
+        name: "arb accumulates",
+        code: "109,5,109,-2,204,-3,99",
+        runs: &[(&[], Halt(&[109], 109))],
+        mem: &[],
+    },
+    Case {
+        name: "arb position and relative",
+        code: "9,8,209,5,204,0,99,0,3",
+        runs: &[(&[], Halt(&[99], 9))],
+        mem: &[],
+    },
+    // Memory beyond the program.
+    Case {
+        name: "quine",
+        code: "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+        runs: &[(
+            &[],
+            Halt(
+                &[
+                    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+                ],
+                109,
+            ),
+        )],
+        mem: &[(100, 16), (101, 1)],
+    },
+    Case {
+        name: "large numbers",
+        code: "1102,34915192,34915192,7,4,7,99,0",
+        runs: &[(&[], Halt(&[1_219_070_632_396_864], 1102))],
+        mem: &[],
+    },
+    Case {
+        name: "far write",
+        code: "1101,1,2,1000,4,1000,4,5000,99",
+        runs: &[(&[], Halt(&[3, 0], 1101))],
+        mem: &[(1000, 3), (5000, 0)],
+    },
+    // Halting and waiting.
+    Case {
+        name: "halt is final",
+        code: "104,1,99",
+        runs: &[(&[], Halt(&[1], 104)), (&[5], Halt(&[], 104))],
+        mem: &[],
+    },
+    Case {
+        name: "wait resumes",
+        code: "3,11,109,12,203,0,4,11,204,0,99,0,0",
+        runs: &[
+            (&[], Wait(&[])),
+            (&[5], Wait(&[])),
+            (&[6], Halt(&[5, 6], 3)),
+        ],
+        mem: &[(11, 5), (12, 6)],
+    },
+    Case {
+        name: "wait keeps outputs",
+        code: "3,9,1002,9,2,9,4,9,1105,1,0",
+        runs: &[
+            (&[3, 4], Wait(&[6, 8])),
+            (&[], Wait(&[])),
+            (&[5], Wait(&[10])),
+        ],
+        mem: &[(9, 10)],
+    },
+    // Errors.
+    Case {
+        name: "invalid opcode",
+        code: "42",
+        runs: &[(&[], Fail(InvalidOpcode { pc: 0, instr: 42 }))],
+        mem: &[],
+    },
+    Case {
+        name: "invalid mode",
+        code: "30001,0,0,0,99",
+        runs: &[(
+            &[],
+            Fail(InvalidMode {
+                pc: 0,
+                instr: 30001,
+                mode: 3,
+            }),
+        )],
+        mem: &[],
+    },
+    Case {
+        name: "negative address",
+        code: "104,1,4,-1,99",
+        runs: &[(
+            &[],
+            Fail(NegativeAddress {
+                pc: 2,
+                instr: 4,
+                addr: -1,
+            }),
+        )],
+        mem: &[],
+    },
+    Case {
+        name: "negative relative address",
+        code: "204,-1,99",
+        runs: &[(
+            &[],
+            Fail(NegativeAddress {
+                pc: 0,
+                instr: 204,
+                addr: -1,
+            }),
+        )],
+        mem: &[],
+    },
+    Case {
+        name: "address too large",
+        code: "1101,1,1,100000000000000000,99",
+        runs: &[(
+            &[],
+            Fail(AddressTooLarge {
+                pc: 0,
+                addr: 100_000_000_000_000_000,
+            }),
+        )],
+        mem: &[],
+    },
+    Case {
+        name: "relative address too large",
+        code: "109,16777215,204,1,99",
+        runs: &[(
+            &[],
+            Fail(AddressTooLarge {
+                pc: 2,
+                addr: 16_777_216,
+            }),
+        )],
+        mem: &[],
+    },
+    Case {
+        name: "negative pc",
+        code: "1106,0,-5",
+        runs: &[(&[], Fail(InvalidPc { pc: -5 }))],
+        mem: &[],
+    },
+];
+
+#[test]
+fn conformance() {
+    for case in CASES {
+        for (backend, mut vm) in backends(case.code) {
+            for (n, (input, expect)) in case.runs.iter().enumerate() {
+                assert_eq!(
+                    expect.run(),
+                    vm.run(input),
+                    "{}, run {} on {}",
+                    case.name,
+                    n,
+                    backend
+                );
+            }
+            for &(addr, v) in case.mem {
+                assert_eq!(
+                    v,
+                    vm.peek(addr),
+                    "{}, cell {} on {}",
+                    case.name,
+                    addr,
+                    backend
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn overflow() {
+    // 2^62 * 2 does not fit into 64 bits.
+    for (backend, mut vm) in backends("1102,4611686018427387904,2,0,99") {
+        if backend != "i128" && backend != "bigint" {
+            assert_eq!(Err(Overflow { pc: 0 }), vm.run(&[]), "{}", backend);
+        }
+    }
+}
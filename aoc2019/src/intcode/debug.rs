@@ -0,0 +1,311 @@
+use super::history::{History, Undo};
+use super::snapshot::Snapshot;
+use super::{Access, InstrState, Intcode, IntcodeError, WatchHit};
+use std::collections::{BTreeSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Step,
+    Breakpoint(isize),
+    Wait,
+    Halt(isize),
+    Watch(WatchHit),
+    /// Rewound to an `out` instruction, which produced this value.
+    Output(isize),
+    /// Rewound as far back as the history goes.
+    Start,
+}
+
+/// Wraps an `Intcode` with breakpoints and an input queue that can be filled while the
+/// program is paused. Executed instructions are recorded so they can be undone again;
+/// changes made directly to `ic` are not, use `poke` to change memory undoably.
+pub struct Debugger {
+    pub ic: Intcode,
+    breakpoints: BTreeSet<isize>,
+    input: VecDeque<isize>,
+    output: Vec<isize>,
+    history: History,
+}
+
+impl Debugger {
+    pub fn new(ic: Intcode) -> Self {
+        Debugger {
+            ic,
+            breakpoints: BTreeSet::new(),
+            input: VecDeque::new(),
+            output: Vec::new(),
+            history: History::new(10_000, 100_000),
+        }
+    }
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        let mut dbg = Debugger::new(snapshot.ic);
+        dbg.feed(&snapshot.input);
+        dbg
+    }
+    pub fn snapshot(&self) -> Snapshot {
+        self.ic.snapshot(&self.pending_input().collect::<Vec<_>>())
+    }
+    pub fn breakpoints(&self) -> impl Iterator<Item = isize> + '_ {
+        self.breakpoints.iter().cloned()
+    }
+    pub fn add_breakpoint(&mut self, pc: isize) -> bool {
+        self.breakpoints.insert(pc)
+    }
+    pub fn remove_breakpoint(&mut self, pc: isize) -> bool {
+        self.breakpoints.remove(&pc)
+    }
+    pub fn feed(&mut self, input: &[isize]) {
+        self.input.extend(input);
+    }
+    pub fn pending_input(&self) -> impl Iterator<Item = isize> + '_ {
+        self.input.iter().cloned()
+    }
+    /// Copies the machine every `interval` instructions and keeps undo information for the
+    /// last `depth` ones. Going back further replays from the copies.
+    pub fn set_history(&mut self, interval: u64, depth: usize) {
+        self.history.interval = interval;
+        self.history.depth = depth;
+    }
+    /// Sets a memory cell. Stepping back undoes the change before the instruction executed
+    /// last.
+    pub fn poke(&mut self, addr: usize, v: isize) -> Result<(), IntcodeError> {
+        self.history.poke(&mut self.ic, addr, v)
+    }
+    /// Instructions executed so far, minus the ones stepped back over.
+    pub fn time(&self) -> u64 {
+        self.history.time()
+    }
+    /// Output produced since the last call.
+    pub fn take_output(&mut self) -> Vec<isize> {
+        std::mem::take(&mut self.output)
+    }
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> Result<Stop, IntcodeError> {
+        let stop = match self.history.step(&mut self.ic, &mut self.input)? {
+            InstrState::Run => Stop::Step,
+            InstrState::Wait => Stop::Wait,
+            InstrState::Output(o) => {
+                self.output.push(o);
+                Stop::Step
+            }
+            InstrState::Halt(h) => Stop::Halt(h),
+        };
+        Ok(self.ic.take_watch_stop().map_or(stop, Stop::Watch))
+    }
+    /// Runs until a breakpoint is reached, more input is needed or the program halts. The
+    /// instruction at the current pc is always executed, even if it has a breakpoint.
+    pub fn cont(&mut self) -> Result<Stop, IntcodeError> {
+        loop {
+            match self.step()? {
+                Stop::Step => (),
+                stop => return Ok(stop),
+            }
+            if self.breakpoints.contains(&self.ic.pc()) {
+                return Ok(Stop::Breakpoint(self.ic.pc()));
+            }
+        }
+    }
+    fn undo(&mut self) -> Result<Option<Undo>, IntcodeError> {
+        let undo = self.history.undo(&mut self.ic, &mut self.input)?;
+        if let Some(Undo {
+            output: Some(_), ..
+        }) = undo
+        {
+            self.output.pop();
+        }
+        Ok(undo)
+    }
+    /// Undoes the last instruction. Output that was already taken stays taken.
+    pub fn step_back(&mut self) -> Result<Stop, IntcodeError> {
+        Ok(match self.undo()? {
+            Some(_) => Stop::Step,
+            None => Stop::Start,
+        })
+    }
+    /// Runs backwards until the pc is at the last instruction that wrote to `addr`.
+    pub fn back_to_write(&mut self, addr: usize) -> Result<Stop, IntcodeError> {
+        loop {
+            let new = self.ic.peek(addr);
+            match self.undo()? {
+                Some(undo) => match undo.write {
+                    Some((a, old)) if a == addr => {
+                        return Ok(Stop::Watch(WatchHit {
+                            pc: undo.pc,
+                            addr,
+                            access: Access::Write,
+                            old,
+                            new,
+                        }))
+                    }
+                    _ => (),
+                },
+                None => return Ok(Stop::Start),
+            }
+        }
+    }
+    /// Runs backwards until the pc is at the last `out` instruction executed.
+    pub fn back_to_output(&mut self) -> Result<Stop, IntcodeError> {
+        while let Some(undo) = self.undo()? {
+            if let Some(v) = undo.output {
+                return Ok(Stop::Output(v));
+            }
+        }
+        Ok(Stop::Start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn breakpoints() -> Result<(), Box<dyn Error>> {
+        let mut dbg = Debugger::new(Intcode::from_asm(
+            "
+                in -> [x]
+            loop:
+                out [x]
+                add [x], #-1 -> [x]
+                jnz [x], #loop
+                halt
+            x:  data 0
+            ",
+        )?);
+        assert_eq!(Stop::Wait, dbg.cont()?);
+        dbg.feed(&[3]);
+        assert!(dbg.add_breakpoint(2));
+        assert_eq!(Stop::Breakpoint(2), dbg.cont()?);
+        assert_eq!(Stop::Breakpoint(2), dbg.cont()?);
+        assert_eq!(Stop::Breakpoint(2), dbg.cont()?);
+        assert_eq!(vec![3, 2], dbg.take_output());
+        assert_eq!(1, dbg.ic.memory()[12]);
+        assert!(dbg.remove_breakpoint(2));
+        assert_eq!(Stop::Step, dbg.step()?);
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![1], dbg.take_output());
+        Ok(())
+    }
+    #[test]
+    fn reverse() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_asm(
+            "
+                in -> [x]
+            loop:
+                out [x]
+                add [x], #-1 -> [x]
+                jnz [x], #loop
+                halt
+            x:  data 0
+            ",
+        )?;
+        let mut dbg = Debugger::new(ic.clone());
+        dbg.feed(&[3]);
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(10, dbg.time());
+
+        assert_eq!(Stop::Output(1), dbg.back_to_output()?);
+        assert_eq!((2, 1), (dbg.ic.pc(), dbg.ic.peek(12)));
+        assert_eq!(vec![3, 2], dbg.take_output());
+        let hit = WatchHit {
+            pc: 4,
+            addr: 12,
+            access: Access::Write,
+            old: 2,
+            new: 1,
+        };
+        assert_eq!(Stop::Watch(hit), dbg.back_to_write(12)?);
+        assert_eq!((4, 2), (dbg.ic.pc(), dbg.ic.peek(12)));
+        assert_eq!(Stop::Step, dbg.step_back()?);
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![2, 1], dbg.take_output());
+
+        // Only three instructions can be undone directly, the rest is replayed.
+        let mut dbg = Debugger::new(ic);
+        dbg.set_history(4, 3);
+        dbg.feed(&[3]);
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        let pcs: Vec<_> = std::iter::from_fn(|| match dbg.step_back() {
+            Ok(Stop::Step) => Some(dbg.ic.pc()),
+            _ => None,
+        })
+        .collect();
+        assert_eq!(vec![8, 4, 2, 8, 4, 2, 8, 4, 2, 0], pcs);
+        assert_eq!(Stop::Start, dbg.step_back()?);
+        assert_eq!((0, 0), (dbg.time(), dbg.ic.peek(12)));
+        assert_eq!(vec![3], dbg.pending_input().collect::<Vec<_>>());
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![3, 2, 1], dbg.take_output());
+        Ok(())
+    }
+    #[test]
+    fn poke() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_asm(
+            "
+                in -> [x]
+            loop:
+                out [x]
+                add [x], #-1 -> [x]
+                jnz [x], #loop
+                halt
+            x:  data 0
+            ",
+        )?;
+        let mut dbg = Debugger::new(ic);
+        dbg.set_history(2, 2);
+        dbg.feed(&[3]);
+        assert_eq!(Stop::Step, dbg.step()?);
+        dbg.poke(12, 5)?;
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![5, 4, 3, 2, 1], dbg.take_output());
+
+        // Replaying from the copies applies the poke again, going further back reverts it.
+        while dbg.time() > 1 {
+            assert_eq!(Stop::Step, dbg.step_back()?);
+        }
+        assert_eq!((2, 5), (dbg.ic.pc(), dbg.ic.peek(12)));
+        assert_eq!(Stop::Step, dbg.step_back()?);
+        assert_eq!((2, 3, 1), (dbg.ic.pc(), dbg.ic.peek(12), dbg.time()));
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![3, 2, 1], dbg.take_output());
+        Ok(())
+    }
+    #[test]
+    fn limits() -> Result<(), Box<dyn Error>> {
+        // Squares its input three times, which wraps around to 0 for 2^20.
+        let mut ic = Intcode::from_asm(
+            "
+                in -> [x]
+                mul [x], [x] -> [x]
+                mul [x], [x] -> [x]
+                mul [x], [x] -> [x]
+                out [x]
+                halt
+            x:  data 0
+            ",
+        )?;
+        ic.set_wrap(true);
+        ic.set_address_limit(100);
+        let mut dbg = Debugger::new(ic);
+        dbg.set_history(10, 1);
+        dbg.feed(&[1 << 20]);
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![0], dbg.take_output());
+
+        // Stepping back replays the wrapping multiplies from the first copy.
+        assert_eq!(Stop::Output(0), dbg.back_to_output()?);
+        assert_eq!(Stop::Step, dbg.step_back()?);
+        assert_eq!((10, 0), (dbg.ic.pc(), dbg.ic.peek(17)));
+        assert_eq!(Stop::Step, dbg.step_back()?);
+        assert_eq!((6, 1 << 40), (dbg.ic.pc(), dbg.ic.peek(17)));
+        assert_eq!(Stop::Halt(3), dbg.cont()?);
+        assert_eq!(vec![0], dbg.take_output());
+
+        assert_eq!(
+            Err(IntcodeError::AddressTooLarge { pc: 16, addr: 100 }),
+            dbg.poke(100, 1)
+        );
+        dbg.poke(99, 1)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use super::IntcodeError;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+impl Mode {
+    pub fn from_digit(d: isize) -> Option<Self> {
+        match d {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+    pub fn digit(self) -> isize {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Mul,
+    In,
+    Out,
+    Jnz,
+    Jz,
+    Lt,
+    Eq,
+    Arb,
+    Halt,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 10] = [
+        Opcode::Add,
+        Opcode::Mul,
+        Opcode::In,
+        Opcode::Out,
+        Opcode::Jnz,
+        Opcode::Jz,
+        Opcode::Lt,
+        Opcode::Eq,
+        Opcode::Arb,
+        Opcode::Halt,
+    ];
+
+    pub fn from_code(code: isize) -> Option<Self> {
+        Opcode::ALL.iter().cloned().find(|op| op.code() == code)
+    }
+    pub fn code(self) -> isize {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Mul => 2,
+            Opcode::In => 3,
+            Opcode::Out => 4,
+            Opcode::Jnz => 5,
+            Opcode::Jz => 6,
+            Opcode::Lt => 7,
+            Opcode::Eq => 8,
+            Opcode::Arb => 9,
+            Opcode::Halt => 99,
+        }
+    }
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "add",
+            Opcode::Mul => "mul",
+            Opcode::In => "in",
+            Opcode::Out => "out",
+            Opcode::Jnz => "jnz",
+            Opcode::Jz => "jz",
+            Opcode::Lt => "lt",
+            Opcode::Eq => "eq",
+            Opcode::Arb => "arb",
+            Opcode::Halt => "halt",
+        }
+    }
+    /// Number of parameters following the opcode.
+    pub fn params(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Mul | Opcode::Lt | Opcode::Eq => 3,
+            Opcode::Jnz | Opcode::Jz => 2,
+            Opcode::In | Opcode::Out | Opcode::Arb => 1,
+            Opcode::Halt => 0,
+        }
+    }
+    /// Whether the last parameter is written to.
+    pub fn writes(self) -> bool {
+        matches!(
+            self,
+            Opcode::Add | Opcode::Mul | Opcode::In | Opcode::Lt | Opcode::Eq
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instr {
+    pub op: Opcode,
+    pub modes: [Mode; 3],
+}
+
+impl Instr {
+    /// Decodes the raw instruction `instr` found at `pc`.
+    ///
+    /// Only the modes of parameters the opcode actually uses are checked, the remaining
+    /// digits are ignored just like the VM does.
+    pub fn decode(pc: isize, instr: isize) -> Result<Self, IntcodeError> {
+        let op = Opcode::from_code(instr % 100).ok_or(IntcodeError::InvalidOpcode { pc, instr })?;
+        let mut modes = [Mode::Position; 3];
+        let mut digits = instr / 100;
+        for mode in modes.iter_mut().take(op.params()) {
+            *mode = Mode::from_digit(digits % 10).ok_or(IntcodeError::InvalidMode {
+                pc,
+                instr,
+                mode: digits % 10,
+            })?;
+            digits /= 10;
+        }
+        Ok(Instr { op, modes })
+    }
+    pub fn encode(self) -> isize {
+        self.modes
+            .iter()
+            .rev()
+            .fold(0, |acc, m| acc * 10 + m.digit())
+            * 100
+            + self.op.code()
+    }
+    /// Length in memory cells, including the opcode itself.
+    pub fn size(self) -> usize {
+        self.op.params() + 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Operand {
+    pub mode: Mode,
+    pub value: isize,
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mode {
+            Mode::Position => write!(f, "[{}]", self.value),
+            Mode::Immediate => write!(f, "#{}", self.value),
+            Mode::Relative => write!(f, "rel[{:+}]", self.value),
+        }
+    }
+}
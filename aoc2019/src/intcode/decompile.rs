@@ -0,0 +1,719 @@
+use super::cfg::{Block, Cfg, Flow};
+use super::disasm::Line;
+use super::{Intcode, Mode, Opcode, Operand};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// Calls follow the convention of the puzzle inputs: the caller stores the return address
+// at rel[0] and the arguments above it, the callee allocates its frame with `arb #n` and
+// leaves its result in the first argument slot. Relative operands are named after their
+// slot counted from the base at function entry: `a` for parameters, `v` for locals and
+// `t` for slots above the frame, which hold call arguments and results.
+
+fn imm(arg: &Operand) -> Option<isize> {
+    match arg.mode {
+        Mode::Immediate => Some(arg.value),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Region {
+    Loop { head: usize, last: usize },
+    If { last: usize },
+}
+
+impl Region {
+    fn last(self) -> usize {
+        match self {
+            Region::Loop { last, .. } | Region::If { last } => last,
+        }
+    }
+}
+
+enum Out {
+    Text(usize, String),
+    Label(usize, usize),
+}
+
+struct Func<'a> {
+    cfg: &'a Cfg,
+    entry: usize,
+    blocks: Vec<usize>,
+    index: HashMap<usize, usize>,
+    frame: isize,
+    /// Relative base before each instruction, counted from the base at entry.
+    delta: HashMap<usize, Option<isize>>,
+    params: BTreeSet<isize>,
+}
+
+impl<'a> Func<'a> {
+    fn new(cfg: &'a Cfg, entry: usize) -> Self {
+        let mut entry_delta = BTreeMap::new();
+        let mut delta = HashMap::new();
+        let mut todo = vec![(entry, Some(0))];
+        while let Some((start, d)) = todo.pop() {
+            let block = match cfg.blocks.get(&start) {
+                Some(block) => block,
+                None => continue,
+            };
+            let d = match entry_delta.get(&start) {
+                Some(None) => continue,
+                Some(&old) if old == d => continue,
+                Some(_) => None,
+                None => d,
+            };
+            entry_delta.insert(start, d);
+            let mut cur = d;
+            for line in &block.instrs {
+                delta.insert(line.addr(), cur);
+                if let Line::Instr {
+                    op: Opcode::Arb,
+                    args,
+                    ..
+                } = line
+                {
+                    cur = imm(&args[0]).and_then(|v| cur.map(|c| c + v));
+                }
+            }
+            let succs = match block.flow {
+                Flow::Call { ret, .. } | Flow::IndirectCall { ret } => vec![ret],
+                flow => flow.successors(),
+            };
+            todo.extend(succs.into_iter().map(|s| (s, cur)));
+        }
+        let blocks: Vec<usize> = entry_delta.keys().cloned().collect();
+        let index = blocks.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+        // The first frame allocated while the base is still the one at entry.
+        let frame = blocks
+            .iter()
+            .flat_map(|b| &cfg.blocks[b].instrs)
+            .find_map(|line| match line {
+                Line::Instr {
+                    addr,
+                    op: Opcode::Arb,
+                    args,
+                } if delta[addr] == Some(0) => imm(&args[0]).filter(|&n| n > 0),
+                _ => None,
+            })
+            .unwrap_or(0);
+        let mut func = Func {
+            cfg,
+            entry,
+            blocks,
+            index,
+            frame,
+            delta,
+            params: BTreeSet::new(),
+        };
+        func.find_params();
+        func
+    }
+    fn instrs(&self) -> impl Iterator<Item = (usize, Opcode, &[Operand])> {
+        self.blocks
+            .iter()
+            .flat_map(move |b| &self.cfg.blocks[b].instrs)
+            .filter_map(|line| match line {
+                Line::Instr { addr, op, args } => Some((*addr, *op, &args[..])),
+                Line::Data { .. } => None,
+            })
+    }
+    /// Frame slots read before they are written, in address order.
+    fn find_params(&mut self) {
+        let mut params = BTreeSet::new();
+        let mut written = BTreeSet::new();
+        for (addr, op, args) in self.instrs() {
+            for (i, arg) in args.iter().enumerate() {
+                let s = match self.slot_of(addr, arg) {
+                    Some(s) if s >= 1 && s < self.frame => s,
+                    _ => continue,
+                };
+                if op.writes() && i == args.len() - 1 {
+                    written.insert(s);
+                } else if !written.contains(&s) {
+                    params.insert(s);
+                }
+            }
+        }
+        self.params = params;
+    }
+    fn slot_of(&self, addr: usize, arg: &Operand) -> Option<isize> {
+        match arg.mode {
+            Mode::Relative => self
+                .delta
+                .get(&addr)
+                .cloned()
+                .flatten()
+                .map(|d| d + arg.value),
+            _ => None,
+        }
+    }
+    fn slot(&self, s: isize) -> String {
+        if s < 0 {
+            format!("up{}", -s)
+        } else if s == 0 {
+            "ret".to_string()
+        } else if s < self.frame {
+            let kind = if self.params.contains(&s) { "a" } else { "v" };
+            format!("{}{}", kind, s)
+        } else {
+            format!("t{}", s - self.frame)
+        }
+    }
+    fn operand(&self, addr: usize, arg: &Operand) -> String {
+        match arg.mode {
+            Mode::Immediate => arg.value.to_string(),
+            Mode::Position => format!("mem[{}]", arg.value),
+            Mode::Relative => match self.slot_of(addr, arg) {
+                Some(s) => self.slot(s),
+                None => format!("rel[{}]", arg.value),
+            },
+        }
+    }
+    fn expr(&self, addr: usize, op: Opcode, args: &[Operand]) -> String {
+        let (a, b) = (self.operand(addr, &args[0]), self.operand(addr, &args[1]));
+        match (op, imm(&args[0]), imm(&args[1])) {
+            (Opcode::Add, Some(0), _) => b,
+            (Opcode::Add, _, Some(0)) => a,
+            (Opcode::Add, _, Some(v)) if v < 0 => format!("{} - {}", a, v.unsigned_abs()),
+            (Opcode::Add, Some(v), _) if v < 0 => format!("{} - {}", b, v.unsigned_abs()),
+            (Opcode::Add, _, _) => format!("{} + {}", a, b),
+            (Opcode::Mul, Some(0), _) | (Opcode::Mul, _, Some(0)) => "0".to_string(),
+            (Opcode::Mul, Some(1), _) => b,
+            (Opcode::Mul, _, Some(1)) => a,
+            (Opcode::Mul, Some(-1), _) => format!("-{}", b),
+            (Opcode::Mul, _, Some(-1)) => format!("-{}", a),
+            (Opcode::Mul, _, _) => format!("{} * {}", a, b),
+            (Opcode::Lt, _, _) => format!("{} < {}", a, b),
+            _ => format!("{} == {}", a, b),
+        }
+    }
+    /// Conditions under which the jump ending `block` is taken and not taken, and the
+    /// address of a comparison folded into them.
+    fn cond(&self, block: &Block) -> (String, String, Option<usize>) {
+        let n = block.instrs.len();
+        let (addr, op, c) = match &block.instrs[n - 1] {
+            Line::Instr { addr, op, args } => (*addr, *op, args[0]),
+            Line::Data { .. } => unreachable!(),
+        };
+        let (yes, no, fused) = match block.instrs.get(n.wrapping_sub(2)) {
+            Some(Line::Instr {
+                addr: cmp,
+                op: cmp_op @ (Opcode::Lt | Opcode::Eq),
+                args,
+            }) if args[2] == c => {
+                let (a, b) = (self.operand(*cmp, &args[0]), self.operand(*cmp, &args[1]));
+                let (t, f) = match cmp_op {
+                    Opcode::Lt => ("<", ">="),
+                    _ => ("==", "!="),
+                };
+                (
+                    format!("{} {} {}", a, t, b),
+                    format!("{} {} {}", a, f, b),
+                    Some(*cmp),
+                )
+            }
+            _ => {
+                let c = self.operand(addr, &c);
+                (format!("{} != 0", c), format!("{} == 0", c), None)
+            }
+        };
+        match op {
+            Opcode::Jnz => (yes, no, fused),
+            _ => (no, yes, fused),
+        }
+    }
+    /// Value an instruction stores.
+    fn value(&self, addr: usize, op: Opcode, args: &[Operand]) -> String {
+        match op {
+            Opcode::In => "input()".to_string(),
+            _ => self.expr(addr, op, args),
+        }
+    }
+    /// Whether the slot is written before `addr`, by an instruction or as a call result.
+    fn stored_before(&self, addr: usize, slot: isize) -> bool {
+        let written = self.instrs().any(|(a, op, args)| {
+            a < addr && op.writes() && self.slot_of(a, &args[args.len() - 1]) == Some(slot)
+        });
+        let result = slot == self.frame + 1
+            && self.blocks.iter().any(|b| {
+                let block = &self.cfg.blocks[b];
+                *b < addr && matches!(block.flow, Flow::Call { .. } | Flow::IndirectCall { .. })
+            });
+        written || result
+    }
+    /// Statement for a call at the end of `block`, with the stores it absorbs. Argument
+    /// slots that were never stored are left out, or shown as `_` before stored ones.
+    fn call(&self, block: &Block, callee: String, nparams: isize) -> (String, BTreeSet<usize>) {
+        let ret = match block.flow {
+            Flow::Call { ret, .. } | Flow::IndirectCall { ret } => ret as isize,
+            _ => unreachable!(),
+        };
+        let mut skip = BTreeSet::new();
+        let mut args = BTreeMap::new();
+        let instrs: Vec<_> = block.instrs.iter().collect();
+        for (i, line) in instrs.iter().enumerate() {
+            let (addr, op, ops) = match line {
+                Line::Instr { addr, op, args } if op.writes() => (*addr, *op, args),
+                _ => continue,
+            };
+            let dest = ops[ops.len() - 1];
+            let s = match self.slot_of(addr, &dest) {
+                Some(s) if s >= self.frame => s - self.frame,
+                _ => continue,
+            };
+            let value = self.value(addr, op, ops);
+            if s == 0 && value == ret.to_string() {
+                skip.insert(addr);
+                continue;
+            }
+            let read_later = instrs[i + 1..].iter().any(|l| match l {
+                Line::Instr { addr, op, args } => args
+                    .iter()
+                    .take(args.len() - op.writes() as usize)
+                    .any(|a| self.slot_of(*addr, a) == Some(s + self.frame)),
+                Line::Data { .. } => false,
+            });
+            if s > 0 && !read_later {
+                skip.insert(addr);
+                args.insert(s, value);
+            }
+        }
+        let at = instrs[instrs.len() - 1].addr();
+        let n = args.keys().next_back().cloned().unwrap_or(0).max(nparams);
+        let mut args: Vec<_> = (1..=n)
+            .map(|k| {
+                args.remove(&k).or_else(|| {
+                    Some(format!("t{}", k)).filter(|_| self.stored_before(at, k + self.frame))
+                })
+            })
+            .collect();
+        while args.last() == Some(&None) {
+            args.pop();
+        }
+        let args: Vec<_> = args
+            .into_iter()
+            .map(|a| a.unwrap_or_else(|| "_".to_string()))
+            .collect();
+        (format!("t1 = {}({})", callee, args.join(", ")), skip)
+    }
+    /// Target of the jump ending `block`, and the store it absorbs. A constant target that
+    /// the CFG did not follow was patched, so it is whatever was last stored there.
+    fn target(&self, block: &Block) -> (String, Option<usize>) {
+        let (addr, arg) = match block.instrs.last() {
+            Some(Line::Instr { addr, args, .. }) => (*addr, args[1]),
+            _ => unreachable!(),
+        };
+        if arg.mode != Mode::Immediate || arg.value < 0 {
+            return (self.operand(addr, &arg), None);
+        }
+        let cell = Operand {
+            mode: Mode::Position,
+            value: addr as isize + 2,
+        };
+        let store = block.instrs.iter().rev().find_map(|line| match line {
+            Line::Instr { addr, op, args } if op.writes() && args[args.len() - 1] == cell => {
+                Some((self.value(*addr, *op, args), Some(*addr)))
+            }
+            _ => None,
+        });
+        store.unwrap_or_else(|| (self.operand(addr, &cell), None))
+    }
+    fn stmt(&self, addr: usize, op: Opcode, args: &[Operand]) -> Option<String> {
+        let dest = || self.operand(addr, &args[args.len() - 1]);
+        Some(match op {
+            Opcode::Add | Opcode::Mul | Opcode::Lt | Opcode::Eq => {
+                let (dest, expr) = (dest(), self.expr(addr, op, args));
+                if dest == expr {
+                    return None;
+                }
+                format!("{} = {}", dest, expr)
+            }
+            Opcode::In => format!("{} = input()", dest()),
+            Opcode::Out => format!("output({})", self.operand(addr, &args[0])),
+            Opcode::Arb if imm(&args[0]).is_some() && self.delta[&addr].is_some() => return None,
+            Opcode::Arb => format!("base += {}", self.operand(addr, &args[0])),
+            Opcode::Jnz | Opcode::Jz => return None,
+            Opcode::Halt => "halt".to_string(),
+        })
+    }
+    fn succs(&self, start: usize) -> Vec<usize> {
+        match self.cfg.blocks[&start].flow {
+            Flow::Call { ret, .. } | Flow::IndirectCall { ret } => vec![ret],
+            flow => flow.successors(),
+        }
+    }
+    /// Loops from back edges and ifs from forward branches, as long as they nest.
+    fn regions(&self) -> BTreeMap<usize, Vec<Region>> {
+        let nests = |(h, l): (usize, usize), (h2, l2): (usize, usize)| {
+            l < h2 || l2 < h || (h2 <= h && l <= l2) || (h <= h2 && l2 <= l)
+        };
+        let succs: Vec<Vec<usize>> = self
+            .blocks
+            .iter()
+            .map(|&b| {
+                let succs = self.succs(b).into_iter();
+                succs.filter_map(|s| self.index.get(&s).cloned()).collect()
+            })
+            .collect();
+        let reaches = |from: usize, to: usize| {
+            let mut seen = BTreeSet::new();
+            let mut todo = vec![from];
+            while let Some(i) = todo.pop() {
+                if i == to {
+                    return true;
+                }
+                if seen.insert(i) {
+                    todo.extend(&succs[i]);
+                }
+            }
+            false
+        };
+        let mut heads = BTreeMap::new();
+        for (i, succs) in succs.iter().enumerate() {
+            for &j in succs {
+                match j {
+                    // Only edges closing a cycle make a loop.
+                    j if j <= i && reaches(j, i) => {
+                        let last = heads.entry(j).or_insert(i);
+                        *last = (*last).max(i);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        let mut loops: Vec<(usize, usize)> = Vec::new();
+        let mut cands: Vec<_> = heads.into_iter().collect();
+        cands.sort_by_key(|&(h, l)| (h, Reverse(l)));
+        for cand in cands {
+            if loops.iter().all(|&l| nests(cand, l)) {
+                loops.push(cand);
+            }
+        }
+        let mut ifs: Vec<(usize, usize)> = Vec::new();
+        for (i, &start) in self.blocks.iter().enumerate() {
+            let (taken, fall) = match self.cfg.blocks[&start].flow {
+                Flow::Branch { taken, fall } => (taken, fall),
+                _ => continue,
+            };
+            let t = match self.index.get(&taken) {
+                Some(&t) if t > i + 1 && self.blocks.get(i + 1) == Some(&fall) => t,
+                _ => continue,
+            };
+            let cand = (i, t - 1);
+            // The end of a loop body repeats it, so an if can not end there.
+            let fits_loops = loops
+                .iter()
+                .all(|&(h, l)| nests(cand, (h, l)) && !(h <= i && i <= l && t > l));
+            // Nor can its body be entered other than through the header.
+            let closed = succs
+                .iter()
+                .enumerate()
+                .all(|(k, succs)| (i <= k && k < t) || succs.iter().all(|&s| s <= i || s >= t));
+            if fits_loops && closed && ifs.iter().all(|&f| nests(cand, f)) {
+                ifs.push(cand);
+            }
+        }
+        let mut regions: BTreeMap<usize, Vec<Region>> = BTreeMap::new();
+        for (head, last) in loops {
+            regions
+                .entry(head)
+                .or_default()
+                .push(Region::Loop { head, last });
+        }
+        for (head, last) in ifs {
+            regions.entry(head).or_default().push(Region::If { last });
+        }
+        regions
+    }
+    fn jump(
+        &self,
+        i: usize,
+        to: usize,
+        open: &[Region],
+        gotos: &mut BTreeSet<usize>,
+    ) -> Option<String> {
+        let j = self.index.get(&to).cloned();
+        let innermost = open.iter().rev().find_map(|r| match *r {
+            Region::Loop { head, last } => Some((head, last)),
+            Region::If { .. } => None,
+        });
+        if let (Some((head, last)), Some(j)) = (innermost, j) {
+            if j == head {
+                return if i == last {
+                    None
+                } else {
+                    Some("continue".to_string())
+                };
+            }
+            if j == last + 1 {
+                return Some("break".to_string());
+            }
+        }
+        if j == Some(i + 1) {
+            return None;
+        }
+        gotos.insert(to);
+        Some(format!("goto L{}", to))
+    }
+    fn render(&self, nparams: &HashMap<usize, isize>) -> String {
+        let regions = self.regions();
+        let mut out = Vec::new();
+        let mut open: Vec<Region> = Vec::new();
+        let mut gotos = BTreeSet::new();
+        for (i, &start) in self.blocks.iter().enumerate() {
+            while open.last().is_some_and(|r| r.last() < i) {
+                open.pop();
+                out.push(Out::Text(open.len() + 1, "}".to_string()));
+            }
+            let mut here = regions.get(&i).cloned().unwrap_or_default();
+            here.sort_by_key(|r| Reverse(r.last()));
+            for r in here.iter().filter(|r| matches!(r, Region::Loop { .. })) {
+                out.push(Out::Text(open.len() + 1, "loop {".to_string()));
+                open.push(*r);
+            }
+            let depth = open.len() + 1;
+            out.push(Out::Label(depth, start));
+            let block = &self.cfg.blocks[&start];
+            let mut skip = BTreeSet::new();
+            let mut text = Vec::new();
+            let cond = match block.flow {
+                Flow::Branch { .. }
+                | Flow::Return { fall: Some(_) }
+                | Flow::Indirect { fall: Some(_) } => {
+                    let cond = self.cond(block);
+                    skip.extend(cond.2);
+                    Some(cond)
+                }
+                _ => None,
+            };
+            let call = match block.flow {
+                Flow::Call { target, .. } => {
+                    let n = nparams.get(&target).cloned().unwrap_or(0);
+                    Some(self.call(block, format!("f{}", target), n))
+                }
+                Flow::IndirectCall { .. } => {
+                    let (target, store) = self.target(block);
+                    skip.extend(store);
+                    Some(self.call(block, format!("(*{})", target), 0))
+                }
+                _ => None,
+            };
+            if let Some((_, s)) = &call {
+                skip.extend(s);
+            }
+            for line in &block.instrs {
+                if let Line::Instr { addr, op, args } = line {
+                    if !skip.contains(addr) {
+                        text.extend(self.stmt(*addr, *op, args));
+                    }
+                }
+            }
+            let mut fall = None;
+            match block.flow {
+                Flow::Fall(to) | Flow::Jump(to) => fall = Some(to),
+                Flow::Branch { taken, fall: f } => {
+                    let (yes, no, _) = cond.clone().unwrap();
+                    if let Some(r) = here.iter().find(|r| matches!(r, Region::If { .. })) {
+                        text.push(format!("if {} {{", no));
+                        for t in text.drain(..) {
+                            out.push(Out::Text(depth, t));
+                        }
+                        open.push(*r);
+                        continue;
+                    }
+                    match self.jump(i, taken, &open, &mut gotos) {
+                        Some(j) => {
+                            text.push(format!("if {} {{ {} }}", yes, j));
+                            fall = Some(f);
+                        }
+                        None => text.extend(
+                            self.jump(i, f, &open, &mut gotos)
+                                .map(|j| format!("if {} {{ {} }}", no, j)),
+                        ),
+                    }
+                }
+                Flow::Call { ret, .. } | Flow::IndirectCall { ret } => {
+                    text.push(call.unwrap().0);
+                    fall = Some(ret);
+                }
+                Flow::Return { fall: f } => {
+                    let ret = format!("return {}", self.slot(1));
+                    match (f, &cond) {
+                        (Some(f), Some((yes, _, _))) => {
+                            text.push(format!("if {} {{ {} }}", yes, ret));
+                            fall = Some(f);
+                        }
+                        _ => text.push(ret),
+                    }
+                }
+                Flow::Indirect { fall: f } => {
+                    let jump = format!("goto *{}", self.target(block).0);
+                    match (f, &cond) {
+                        (Some(f), Some((yes, _, _))) => {
+                            text.push(format!("if {} {{ {} }}", yes, jump));
+                            fall = Some(f);
+                        }
+                        _ => text.push(jump),
+                    }
+                }
+                Flow::Halt => (),
+                Flow::Invalid(addr) => text.push(format!("invalid instruction at {}", addr)),
+            }
+            if let Some(to) = fall {
+                text.extend(self.jump(i, to, &open, &mut gotos));
+            }
+            for t in text {
+                out.push(Out::Text(depth, t));
+            }
+        }
+        while open.pop().is_some() {
+            out.push(Out::Text(open.len() + 1, "}".to_string()));
+        }
+
+        let name = if self.entry == 0 {
+            "main".to_string()
+        } else {
+            format!("f{}", self.entry)
+        };
+        let params: Vec<_> = self.params.iter().map(|&s| self.slot(s)).collect();
+        let mut s = format!("fn {}({}) {{\n", name, params.join(", "));
+        for line in out {
+            match line {
+                Out::Text(depth, t) => s += &format!("{}{}\n", "    ".repeat(depth), t),
+                Out::Label(depth, addr) if gotos.contains(&addr) => {
+                    s += &format!("{}L{}:\n", "    ".repeat(depth - 1), addr)
+                }
+                Out::Label(..) => (),
+            }
+        }
+        s += "}\n";
+        s
+    }
+}
+
+/// Pseudo-code for every function in the control-flow graph of `mem`.
+pub fn decompile(mem: &[isize]) -> String {
+    let cfg = Cfg::new(mem);
+    let funcs: Vec<_> = cfg.functions.iter().map(|&f| Func::new(&cfg, f)).collect();
+    let nparams = funcs
+        .iter()
+        .map(|f| (f.entry, f.params.iter().next_back().cloned().unwrap_or(0)))
+        .collect();
+    funcs
+        .iter()
+        .map(|f| f.render(&nparams))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Intcode {
+    pub fn decompile(&self) -> String {
+        decompile(&self.image())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn structure() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_asm(
+            "
+                    arb #stack
+            loop:   in -> [x]
+                    jz [x], #done
+                    add [x], #0 -> rel[1]
+                    add #back, #0 -> rel[0]
+                    jz #0, #abs
+            back:   out rel[1]
+                    jnz #1, #loop
+            done:   halt
+            abs:    arb #2
+                    lt rel[-1], #0 -> rel[0]
+                    jz rel[0], #pos
+                    mul rel[-1], #-1 -> rel[-1]
+            pos:    arb #-2
+                    jz #0, rel[0]
+            x:      data 0
+            stack:  data 0
+            ",
+        )?;
+        assert_eq!(
+            "\
+fn main() {
+    loop {
+        mem[42] = input()
+        if mem[42] == 0 { break }
+        t1 = f24(mem[42])
+        output(t1)
+    }
+    halt
+}
+
+fn f24(a1) {
+    if a1 < 0 {
+        a1 = -a1
+    }
+    return a1
+}
+",
+            ic.decompile()
+        );
+        Ok(())
+    }
+    #[test]
+    fn indirect_call() {
+        assert_eq!(
+            "fn main() {\n    t1 = (*mem[9])()\n    halt\n}\n",
+            decompile(&[21101, 7, 0, 0, 105, 1, 9, 99, 99, 8])
+        );
+    }
+    #[test]
+    fn patched_call() -> Result<(), Box<dyn Error>> {
+        // `apply` calls its first argument on the second, through a patched jump target.
+        let ic = Intcode::from_asm(
+            "
+                    arb #stack
+                    in -> rel[2]
+                    add #neg, #0 -> rel[1]
+                    add #back, #0 -> rel[0]
+                    jz #0, #apply
+            back:   out rel[1]
+                    halt
+            apply:  arb #3
+                    add rel[-2], #0 -> [call+2]
+                    add rel[-1], #0 -> rel[1]
+                    add #done, #0 -> rel[0]
+            call:   jz #0, #0
+            done:   add rel[1], #0 -> rel[-2]
+                    arb #-3
+                    jz #0, rel[0]
+            neg:    arb #2
+                    mul rel[-1], #-1 -> rel[-1]
+                    arb #-2
+                    jz #0, rel[0]
+            stack:  data 0
+            ",
+        )?;
+        assert_eq!(
+            "\
+fn main() {
+    t1 = f18(44, input())
+    output(t1)
+    halt
+}
+
+fn f18(a1, a2) {
+    t1 = (*a1)(a2)
+    a1 = t1
+    return a1
+}
+",
+            ic.decompile()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Instr, Intcode, Opcode, Operand};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line {
+    Instr {
+        addr: usize,
+        op: Opcode,
+        args: Vec<Operand>,
+    },
+    Data {
+        addr: usize,
+        values: Vec<isize>,
+    },
+}
+
+impl Line {
+    pub fn addr(&self) -> usize {
+        match self {
+            Line::Instr { addr, .. } => *addr,
+            Line::Data { addr, .. } => *addr,
+        }
+    }
+    /// Number of memory cells covered by this line.
+    pub fn len(&self) -> usize {
+        match self {
+            Line::Instr { args, .. } => args.len() + 1,
+            Line::Data { values, .. } => values.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Line::Instr { op, args, .. } => {
+                let (srcs, dst) = if op.writes() {
+                    (&args[..args.len() - 1], args.last())
+                } else {
+                    (&args[..], None)
+                };
+                write!(f, "{}", op.mnemonic())?;
+                for (i, arg) in srcs.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { " " } else { ", " }, arg)?;
+                }
+                if let Some(dst) = dst {
+                    write!(f, " -> {}", dst)?;
+                }
+                Ok(())
+            }
+            Line::Data { values, .. } => {
+                write!(f, "data")?;
+                for (i, v) in values.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { " " } else { ", " }, v)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Decodes the instruction at `addr`, if there is a complete one.
+pub fn decode_at(mem: &[isize], addr: usize) -> Option<Line> {
+    let instr = Instr::decode(addr as isize, *mem.get(addr)?).ok()?;
+    let args = mem
+        .get(addr + 1..addr + instr.size())?
+        .iter()
+        .zip(&instr.modes)
+        .map(|(&value, &mode)| Operand { mode, value })
+        .collect();
+    Some(Line::Instr {
+        addr,
+        op: instr.op,
+        args,
+    })
+}
+
+/// Linear sweep over `mem`. Cells that do not decode to a complete instruction are
+/// collected into `data` lines.
+pub fn disassemble(mem: &[isize]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < mem.len() {
+        if let Some(line) = decode_at(mem, addr) {
+            addr += line.len();
+            lines.push(line);
+            continue;
+        }
+        match lines.last_mut() {
+            Some(Line::Data { values, .. }) => values.push(mem[addr]),
+            _ => lines.push(Line::Data {
+                addr,
+                values: vec![mem[addr]],
+            }),
+        }
+        addr += 1;
+    }
+    lines
+}
+
+impl Intcode {
+    pub fn disassemble(&self) -> Vec<Line> {
+        disassemble(&self.image())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn listing(src: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(Intcode::from_str(src)?
+            .disassemble()
+            .iter()
+            .map(|l| format!("{}: {}", l.addr(), l))
+            .collect())
+    }
+
+    #[test]
+    fn modes() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            vec!["0: mul [4], #3 -> [4]", "4: data 33"],
+            listing("1002,4,3,4,33")?
+        );
+        assert_eq!(
+            vec![
+                "0: arb #1",
+                "2: out rel[-1]",
+                "4: add #12, #5 -> rel[+3]",
+                "8: halt"
+            ],
+            listing("109,1,204,-1,21101,12,5,3,99")?
+        );
+        Ok(())
+    }
+    #[test]
+    fn data() -> Result<(), Box<dyn Error>> {
+        assert_eq!(
+            vec![
+                "0: in -> [9]",
+                "2: eq [9], [10] -> [9]",
+                "6: out [9]",
+                "8: halt",
+                "9: data -1, 8"
+            ],
+            listing("3,9,8,9,10,9,4,9,99,-1,8")?
+        );
+        assert_eq!(vec!["0: data 42, 1, 0"], listing("42,1,0")?);
+        assert_eq!(vec!["0: data 301, 0"], listing("301,0")?);
+        Ok(())
+    }
+}
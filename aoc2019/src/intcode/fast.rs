@@ -0,0 +1,308 @@
+use super::wide::Cell;
+use super::{
+    Instr, Intcode, IntcodeError, IntcodeInput, IntcodeOutput, Mode, Opcode, State, ADDRESS_LIMIT,
+};
+
+/// Interpreter that keeps every decoded instruction in a cache next to a plain `Vec`
+/// memory. Writes drop the cached decoding of the written cell, so self-modifying code
+/// behaves exactly as in `Intcode`. Only the step budget is supported, watchpoints,
+/// traces, profiles and deadlines are not. Memory is always dense, so converting a machine
+/// with far away sparse writes allocates everything up to the highest one.
+#[derive(Debug, Clone)]
+pub struct FastIntcode {
+    mem: Vec<isize>,
+    cache: Vec<Option<Instr>>,
+    pc: isize,
+    base: isize,
+    limit: usize,
+    wrap: bool,
+    budget: Option<u64>,
+}
+
+impl From<Vec<isize>> for FastIntcode {
+    fn from(mem: Vec<isize>) -> Self {
+        FastIntcode {
+            cache: vec![None; mem.len()],
+            mem,
+            pc: 0,
+            base: 0,
+            limit: ADDRESS_LIMIT,
+            wrap: false,
+            budget: None,
+        }
+    }
+}
+
+impl From<&Intcode> for FastIntcode {
+    fn from(ic: &Intcode) -> Self {
+        FastIntcode {
+            pc: ic.pc,
+            base: ic.base,
+            limit: ic.limit,
+            wrap: ic.wrap,
+            budget: ic.budget(),
+            ..FastIntcode::from(ic.mem.to_vec())
+        }
+    }
+}
+
+impl FastIntcode {
+    #[inline]
+    fn load(&self, addr: usize) -> isize {
+        self.mem.get(addr).cloned().unwrap_or(0)
+    }
+    #[inline]
+    fn addr(&self, pc: usize, off: usize, mode: Mode) -> Result<usize, IntcodeError> {
+        let addr = match mode {
+            Mode::Position => self.load(pc + off),
+            Mode::Immediate => (pc + off) as isize,
+            Mode::Relative => self
+                .load(pc + off)
+                .checked_add(self.base)
+                .ok_or(IntcodeError::Overflow { pc: pc as isize })?,
+        };
+        if addr < 0 {
+            return Err(IntcodeError::NegativeAddress {
+                pc: pc as isize,
+                instr: self.load(pc),
+                addr,
+            });
+        }
+        if addr as usize >= self.limit {
+            return Err(IntcodeError::AddressTooLarge {
+                pc: pc as isize,
+                addr,
+            });
+        }
+        Ok(addr as usize)
+    }
+    #[inline]
+    fn get(&self, pc: usize, off: usize, mode: Mode) -> Result<isize, IntcodeError> {
+        Ok(self.load(self.addr(pc, off, mode)?))
+    }
+    #[inline]
+    fn set(&mut self, pc: usize, off: usize, mode: Mode, v: isize) -> Result<(), IntcodeError> {
+        let addr = self.addr(pc, off, mode)?;
+        *self.mem(addr) = v;
+        Ok(())
+    }
+    #[inline]
+    fn decode(&mut self, pc: usize) -> Result<Instr, IntcodeError> {
+        if let Some(Some(instr)) = self.cache.get(pc) {
+            return Ok(*instr);
+        }
+        let instr = Instr::decode(pc as isize, self.load(pc))?;
+        if let Some(c) = self.cache.get_mut(pc) {
+            *c = Some(instr);
+        }
+        Ok(instr)
+    }
+    /// Cell at `idx`, growing memory as needed. Invalidates its cached decoding.
+    pub fn mem(&mut self, idx: usize) -> &mut isize {
+        if idx >= self.mem.len() {
+            self.mem.resize(idx + 1, 0);
+            self.cache.resize(idx + 1, None);
+        }
+        self.cache[idx] = None;
+        &mut self.mem[idx]
+    }
+    pub fn memory(&self) -> &[isize] {
+        &self.mem
+    }
+    /// Same as `Intcode::set_budget`.
+    pub fn set_budget(&mut self, steps: Option<u64>) {
+        self.budget = steps;
+    }
+    pub fn budget(&self) -> Option<u64> {
+        self.budget
+    }
+    /// Same as `Intcode::set_address_limit`.
+    pub fn set_address_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+    /// Same as `Intcode::set_wrap`.
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+    pub fn base(&self) -> isize {
+        self.base
+    }
+    /// Same as `Intcode::run_with`.
+    pub fn run_with<I, O>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<State<()>, IntcodeError>
+    where
+        I: IntcodeInput + ?Sized,
+        O: IntcodeOutput + ?Sized,
+    {
+        loop {
+            if self.budget == Some(0) {
+                return Ok(State::Budget((), self.pc));
+            }
+            if self.pc < 0 || self.pc > isize::MAX - 4 {
+                return Err(IntcodeError::InvalidPc { pc: self.pc });
+            }
+            let pc = self.pc as usize;
+            let Instr {
+                op,
+                modes: [p1, p2, p3],
+            } = self.decode(pc)?;
+            match op {
+                Opcode::Add => {
+                    let v = self.get(pc, 1, p1)?.plus(&self.get(pc, 2, p2)?, self.wrap);
+                    let v = v.ok_or(IntcodeError::Overflow { pc: self.pc })?;
+                    self.set(pc, 3, p3, v)?;
+                    self.pc += 4;
+                }
+                Opcode::Mul => {
+                    let v = self.get(pc, 1, p1)?.times(&self.get(pc, 2, p2)?, self.wrap);
+                    let v = v.ok_or(IntcodeError::Overflow { pc: self.pc })?;
+                    self.set(pc, 3, p3, v)?;
+                    self.pc += 4;
+                }
+                Opcode::In => {
+                    let addr = self.addr(pc, 1, p1)?;
+                    match input.input() {
+                        Some(v) => *self.mem(addr) = v,
+                        None => return Ok(State::Wait(())),
+                    }
+                    self.pc += 2;
+                }
+                Opcode::Out => {
+                    output.output(self.get(pc, 1, p1)?);
+                    self.pc += 2;
+                }
+                Opcode::Jnz => {
+                    if self.get(pc, 1, p1)? != 0 {
+                        self.pc = self.get(pc, 2, p2)?;
+                    } else {
+                        self.pc += 3;
+                    }
+                }
+                Opcode::Jz => {
+                    if self.get(pc, 1, p1)? == 0 {
+                        self.pc = self.get(pc, 2, p2)?;
+                    } else {
+                        self.pc += 3;
+                    }
+                }
+                Opcode::Lt => {
+                    let v = (self.get(pc, 1, p1)? < self.get(pc, 2, p2)?) as isize;
+                    self.set(pc, 3, p3, v)?;
+                    self.pc += 4;
+                }
+                Opcode::Eq => {
+                    let v = (self.get(pc, 1, p1)? == self.get(pc, 2, p2)?) as isize;
+                    self.set(pc, 3, p3, v)?;
+                    self.pc += 4;
+                }
+                Opcode::Arb => {
+                    let base = self.base.checked_add(self.get(pc, 1, p1)?);
+                    self.base = base.ok_or(IntcodeError::Overflow { pc: self.pc })?;
+                    self.pc += 2;
+                }
+                Opcode::Halt => return Ok(State::Halt((), self.load(0))),
+            }
+            if let Some(steps) = &mut self.budget {
+                *steps -= 1;
+            }
+        }
+    }
+    pub fn run_input(&mut self, input: &[isize]) -> Result<State<Vec<isize>>, IntcodeError> {
+        let mut out = Vec::new();
+        let state = self.run_with(&mut input.iter(), &mut out)?;
+        Ok(state.map(|_| out))
+    }
+    /// Converts back into a regular machine with the same state.
+    pub fn into_intcode(self) -> Intcode {
+        let mut ic = Intcode::from(self.mem);
+        ic.pc = self.pc;
+        ic.base = self.base;
+        ic.limit = self.limit;
+        ic.wrap = self.wrap;
+        ic.set_budget(self.budget);
+        ic
+    }
+}
+
+impl Intcode {
+    pub fn to_fast(&self) -> FastIntcode {
+        FastIntcode::from(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    fn run(code: &str, input: &[isize]) -> Result<Vec<isize>, Box<dyn Error>> {
+        let mut fast = Intcode::from_str(code)?.to_fast();
+        let out = fast.run_input(input)?;
+        assert_eq!(out, Intcode::from_str(code)?.run_input(input)?);
+        Ok(out.out().clone())
+    }
+
+    #[test]
+    fn day05() -> Result<(), Box<dyn Error>> {
+        let cmp = [
+            ("3,9,8,9,10,9,4,9,99,-1,8", 8),
+            ("3,9,7,9,10,9,4,9,99,-1,8", 7),
+            ("3,3,1108,-1,8,3,4,3,99", 8),
+            ("3,3,1107,-1,8,3,4,3,99", 7),
+        ];
+        for &(code, yes) in &cmp {
+            assert_eq!(vec![1], run(code, &[yes])?);
+            assert_eq!(vec![0], run(code, &[9])?);
+        }
+        for &code in &[
+            "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+            "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+        ] {
+            assert_eq!(vec![0], run(code, &[0])?);
+            assert_eq!(vec![1], run(code, &[5])?);
+        }
+        let large = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,\
+                     1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,\
+                     1105,1,46,98,99";
+        assert_eq!(vec![999], run(large, &[7])?);
+        assert_eq!(vec![1000], run(large, &[8])?);
+        assert_eq!(vec![1001], run(large, &[9])?);
+        Ok(())
+    }
+    #[test]
+    fn self_modifying() -> Result<(), Box<dyn Error>> {
+        // Outputs 6 + 7, turns the add at 0 into a mul and runs it again.
+        let code = "1,21,22,23,4,23,1005,24,20,1101,1,1,0,1101,1,0,24,1105,1,0,99,6,7,0,0";
+        let mut fast = Intcode::from_str(code)?.to_fast();
+        let mut ic = Intcode::from_str(code)?;
+        assert_eq!(State::Halt(vec![13, 42], 2), fast.run_input(&[])?);
+        assert_eq!(ic.run_input(&[])?, State::Halt(vec![13, 42], 2));
+        assert_eq!(&ic.memory()[..], fast.memory());
+
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        assert_eq!(16, run(quine, &[])?.len());
+        assert_eq!(
+            Err(IntcodeError::InvalidOpcode { pc: 4, instr: 42 }),
+            Intcode::from_str("1101,40,2,4,99")?
+                .to_fast()
+                .run_input(&[])
+        );
+        Ok(())
+    }
+    #[test]
+    fn resume() -> Result<(), Box<dyn Error>> {
+        let mut fast = Intcode::from_str("3,9,1002,9,2,9,4,9,1105,1,0")?.to_fast();
+        assert_eq!(State::Wait(vec![6]), fast.run_input(&[3])?);
+        let mut ic = fast.clone().into_intcode();
+        assert_eq!(State::Wait(vec![8]), fast.run_input(&[4])?);
+        assert_eq!(State::Wait(vec![8]), ic.run_input(&[4])?);
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use super::fast::FastIntcode;
+use super::{Intcode, IntcodeError, Opcode, State};
+
+/// Steps each run in `check` may take.
+pub const BUDGET: u64 = 10_000;
+
+fn check_state(ic: &Intcode, state: &State<Vec<isize>>) {
+    let pc = ic.pc();
+    let op = || Opcode::from_code(ic.peek(pc as usize) % 100);
+    match state {
+        State::Wait(_) | State::Halt(..) => {
+            assert!(
+                pc >= 0 && (pc as usize) < ic.memory_len(),
+                "pc {} outside memory",
+                pc
+            );
+        }
+        _ => (),
+    }
+    match state {
+        State::Wait(_) => assert_eq!(Some(Opcode::In), op()),
+        State::Halt(_, code) => {
+            assert_eq!(Some(Opcode::Halt), op());
+            assert_eq!(ic.peek(0), *code);
+        }
+        _ => (),
+    }
+}
+
+type Run = Result<State<Vec<isize>>, IntcodeError>;
+
+/// Runs `input` on both engines and panics unless they agree.
+fn run(ic: &mut Intcode, fast: &mut FastIntcode, input: &[isize]) -> Run {
+    let state = ic.run_input(input);
+    assert_eq!(state, fast.run_input(input), "engines disagree");
+    if let Ok(state) = &state {
+        check_state(ic, state);
+        assert_eq!((ic.pc(), ic.base()), (fast.pc(), fast.base()));
+        assert_eq!(&ic.memory()[..], fast.memory());
+    }
+    state
+}
+
+/// Runs `code` on the default backend and on `FastIntcode` with the first half of `input`
+/// and, if it waits for more, on clones and the originals with the second half. Panics if
+/// the engines disagree, a state is inconsistent with the pc or a clone diverges. Errors
+/// are fine as long as both engines return the same one.
+pub fn check(code: &[isize], input: &[isize]) {
+    let mut ic = Intcode::from(code.to_vec());
+    ic.set_budget(Some(BUDGET));
+    let mut fast = ic.to_fast();
+    let (first, rest) = input.split_at(input.len() / 2);
+    if let Ok(State::Wait(_)) = run(&mut ic, &mut fast, first) {
+        let (mut ic2, mut fast2) = (ic.clone(), fast.clone());
+        assert_eq!(
+            run(&mut ic, &mut fast, rest),
+            run(&mut ic2, &mut fast2, rest)
+        );
+        assert_eq!(
+            (ic.pc(), ic.base(), ic.memory_len()),
+            (ic2.pc(), ic2.base(), ic2.memory_len())
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{Instr, Mode};
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    const OPCODES: [isize; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+    fn operand() -> impl Strategy<Value = isize> {
+        prop_oneof![8 => -4isize..64, 1 => any::<isize>()]
+    }
+
+    fn instr() -> impl Strategy<Value = Vec<isize>> {
+        (
+            prop_oneof![20 => proptest::sample::select(&OPCODES[..]), 1 => Just(99)],
+            [0isize..3, 0..3, 0..3],
+            vec(operand(), 3),
+        )
+            .prop_map(|(code, modes, args)| {
+                let instr = Instr {
+                    op: Opcode::from_code(code).unwrap(),
+                    modes: [
+                        Mode::from_digit(modes[0]).unwrap(),
+                        Mode::from_digit(modes[1]).unwrap(),
+                        Mode::from_digit(modes[2]).unwrap(),
+                    ],
+                };
+                let mut cells = vec![instr.encode()];
+                cells.extend(&args[..instr.op.params()]);
+                cells
+            })
+    }
+
+    /// Valid instructions followed by some data.
+    fn program() -> impl Strategy<Value = Vec<isize>> {
+        (vec(instr(), 1..24), vec(operand(), 0..8)).prop_map(|(instrs, data)| {
+            let mut code = instrs.concat();
+            code.extend(data);
+            code
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn invariants(code in program(), input in vec(-4isize..64, 0..8)) {
+            check(&code, &input);
+        }
+    }
+
+    #[test]
+    fn extremes() {
+        // Relative base and pc at the end of the address space.
+        check(&[109, isize::MAX, 204, 1, 99], &[]);
+        check(&[109, -1, 109, isize::MIN, 99], &[]);
+        check(&[1105, 1, isize::MAX - 2], &[]);
+        check(
+            &[1101, 1105, 0, isize::MAX - 4, 1105, 1, isize::MAX - 4],
+            &[],
+        );
+        // Writes far beyond the address limit.
+        check(&[1101, 1, 1, 100_000_000_000_000_000, 99], &[]);
+        check(&[109, 1 << 40, 21101, 1, 1, 0, 99], &[]);
+        check(&[3, 1 << 30, 99], &[5]);
+    }
+}
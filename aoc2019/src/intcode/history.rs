@@ -0,0 +1,234 @@
+use super::{InstrState, Intcode, IntcodeError};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// How to revert one executed instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Undo {
+    pub pc: isize,
+    pub base: isize,
+    /// The written address and its previous value.
+    pub write: Option<(usize, isize)>,
+    pub input: Option<isize>,
+    pub output: Option<isize>,
+    /// Set for memory changed by hand, which takes no time.
+    pub poke: bool,
+}
+
+/// Memory changed by hand at `time`, replayed together with the instructions.
+struct Poke {
+    time: u64,
+    addr: usize,
+    old: isize,
+    new: isize,
+}
+
+impl Poke {
+    fn undo(&self, pc: isize, base: isize) -> Undo {
+        Undo {
+            pc,
+            base,
+            write: Some((self.addr, self.old)),
+            input: None,
+            output: None,
+            poke: true,
+        }
+    }
+}
+
+/// Checkpoints kept before the older ones are thinned out.
+const MAX_CHECKPOINTS: usize = 64;
+
+struct Checkpoint {
+    time: u64,
+    ic: Intcode,
+    inputs: usize,
+}
+
+/// Undo log of the last `depth` instructions, plus a copy of the machine every `interval`
+/// instructions. Once the log runs dry, it is rebuilt by replaying from the closest
+/// earlier copy with the inputs and pokes recorded since. Only the newer half of the copies
+/// is kept at full density, every other one of the older half is dropped when there are
+/// too many.
+pub(super) struct History {
+    pub interval: u64,
+    pub depth: usize,
+    time: u64,
+    log: VecDeque<Undo>,
+    checkpoints: Vec<Checkpoint>,
+    inputs: Vec<isize>,
+    pokes: Vec<Poke>,
+}
+
+/// Executes one instruction, feeding it from the front of `input`.
+fn exec(
+    ic: &mut Intcode,
+    input: &mut VecDeque<isize>,
+) -> Result<(InstrState, Option<Undo>), IntcodeError> {
+    let (pc, base) = (ic.pc, ic.base);
+    let write = match ic.op() {
+        Ok(instr) if instr.op.writes() => {
+            let n = instr.op.params();
+            let addr = ic.param_addr(n as isize, instr.modes[n - 1])?;
+            Some((addr, ic.peek(addr)))
+        }
+        _ => None,
+    };
+    let (front, len) = (input.front().cloned(), input.len());
+    let state = ic.step(&mut std::iter::from_fn(|| input.pop_front()))?;
+    let undo = Undo {
+        pc,
+        base,
+        write,
+        input: front.filter(|_| input.len() < len),
+        output: None,
+        poke: false,
+    };
+    let undo = match state {
+        InstrState::Run => Some(undo),
+        InstrState::Output(v) => Some(Undo {
+            output: Some(v),
+            ..undo
+        }),
+        InstrState::Wait | InstrState::Halt(_) => None,
+    };
+    Ok((state, undo))
+}
+
+impl History {
+    pub fn new(interval: u64, depth: usize) -> Self {
+        History {
+            interval,
+            depth,
+            time: 0,
+            log: VecDeque::new(),
+            checkpoints: Vec::new(),
+            inputs: Vec::new(),
+            pokes: Vec::new(),
+        }
+    }
+    /// Instructions executed, not counting undone ones.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+    fn push(&mut self, undo: Undo) {
+        while self.log.len() >= self.depth.max(1) {
+            self.log.pop_front();
+        }
+        self.log.push_back(undo);
+    }
+    pub fn step(
+        &mut self,
+        ic: &mut Intcode,
+        input: &mut VecDeque<isize>,
+    ) -> Result<InstrState, IntcodeError> {
+        let due = self.time.is_multiple_of(self.interval.max(1));
+        if due && self.checkpoints.last().is_none_or(|c| c.time < self.time) {
+            // Snapshots keep the address limit and overflow mode, replays behave the same.
+            self.checkpoints.push(Checkpoint {
+                time: self.time,
+                ic: ic.snapshot(&[]).ic,
+                inputs: self.inputs.len(),
+            });
+            if self.checkpoints.len() > MAX_CHECKPOINTS {
+                let old = self.checkpoints.len() / 2;
+                let mut i = 0;
+                self.checkpoints.retain(|_| {
+                    i += 1;
+                    i > old || i % 2 == 1
+                });
+            }
+        }
+        let (state, undo) = exec(ic, input)?;
+        if let Some(undo) = undo {
+            self.inputs.extend(undo.input);
+            self.push(undo);
+            self.time += 1;
+        }
+        Ok(state)
+    }
+    /// Sets `addr` to `v` so that undoing and replaying see the change. Addresses at or
+    /// above the machine's limit are rejected like those of instructions.
+    pub fn poke(&mut self, ic: &mut Intcode, addr: usize, v: isize) -> Result<(), IntcodeError> {
+        if addr >= ic.limit {
+            return Err(IntcodeError::AddressTooLarge {
+                pc: ic.pc,
+                addr: isize::try_from(addr).unwrap_or(isize::MAX),
+            });
+        }
+        let poke = Poke {
+            time: self.time,
+            addr,
+            old: ic.peek(addr),
+            new: v,
+        };
+        *ic.mem(addr) = v;
+        self.push(poke.undo(ic.pc, ic.base));
+        self.pokes.push(poke);
+        Ok(())
+    }
+    /// Replays from the last checkpoint before now to recover the undo log up to now.
+    fn refill(&mut self) -> Result<(), IntcodeError> {
+        let cp = match self.checkpoints.iter().rev().find(|c| c.time < self.time) {
+            Some(cp) => cp,
+            None => return Ok(()),
+        };
+        let mut ic = cp.ic.clone();
+        let mut input = self.inputs[cp.inputs..].iter().cloned().collect();
+        // A checkpoint may already contain the pokes made at its time, applying them again
+        // changes nothing.
+        let start = cp.time;
+        let mut poke = self.pokes.partition_point(|p| p.time < start);
+        for time in start..self.time {
+            while let Some(p) = self.pokes.get(poke).filter(|p| p.time == time) {
+                *ic.mem(p.addr) = p.new;
+                let undo = p.undo(ic.pc, ic.base);
+                self.push(undo);
+                poke += 1;
+            }
+            match exec(&mut ic, &mut input)? {
+                (_, Some(undo)) => self.push(undo),
+                (_, None) => break,
+            }
+        }
+        Ok(())
+    }
+    /// Reverts the last instruction executed on `ic`, returning its consumed input to the
+    /// front of `input`. Returns `None` at the start of the history.
+    pub fn undo(
+        &mut self,
+        ic: &mut Intcode,
+        input: &mut VecDeque<isize>,
+    ) -> Result<Option<Undo>, IntcodeError> {
+        if self.log.is_empty() {
+            self.refill()?;
+        }
+        let undo = match self.log.pop_back() {
+            Some(undo) => undo,
+            None => return Ok(None),
+        };
+        if let Some((addr, old)) = undo.write {
+            *ic.mem(addr) = old;
+        }
+        if undo.poke {
+            self.pokes.pop();
+            // The copy taken at this time may contain the poke.
+            while self.checkpoints.last().is_some_and(|c| c.time >= self.time) {
+                self.checkpoints.pop();
+            }
+            return Ok(Some(undo));
+        }
+        ic.pc = undo.pc;
+        ic.base = undo.base;
+        if let Some(v) = undo.input {
+            input.push_front(v);
+            self.inputs.pop();
+        }
+        self.time -= 1;
+        // Execution may take a different course from here.
+        while self.checkpoints.last().is_some_and(|c| c.time > self.time) {
+            self.checkpoints.pop();
+        }
+        Ok(Some(undo))
+    }
+}
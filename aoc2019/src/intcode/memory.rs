@@ -0,0 +1,386 @@
+use super::codec::{read_usize, write_uvar};
+use super::wide::Cell;
+use super::{Intcode, ADDRESS_LIMIT};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+/// Backing store of a machine. Cells that were never written read as zero; `len` is one
+/// past the highest cell written or loaded.
+pub trait Memory<C = isize>: Send {
+    fn get(&self, addr: usize) -> C;
+    /// Cell at `addr`, allocating it if necessary.
+    fn get_mut(&mut self, addr: usize) -> &mut C;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn to_vec(&self) -> Vec<C>;
+    /// The allocated cells as `(address, cells)` runs in address order, everything else
+    /// reads as zero.
+    fn runs(&self) -> Vec<(usize, &[C])>;
+    /// The cells as a slice, if the backend stores them contiguously.
+    fn as_slice(&self) -> Option<&[C]> {
+        None
+    }
+    fn box_clone(&self) -> Box<dyn Memory<C>>;
+    /// Whether clones share storage until it is written.
+    fn is_cow(&self) -> bool {
+        false
+    }
+}
+
+impl<C> Clone for Box<dyn Memory<C>> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// A single `Vec` covering every address up to the highest one written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dense<C = isize>(Vec<C>);
+
+impl<C> From<Vec<C>> for Dense<C> {
+    fn from(mem: Vec<C>) -> Self {
+        Dense(mem)
+    }
+}
+
+impl<C: Cell> Memory<C> for Dense<C> {
+    fn get(&self, addr: usize) -> C {
+        self.0.get(addr).cloned().unwrap_or_default()
+    }
+    fn get_mut(&mut self, addr: usize) -> &mut C {
+        if addr >= self.0.len() {
+            self.0.resize(addr + 1, C::default());
+        }
+        &mut self.0[addr]
+    }
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+    fn to_vec(&self) -> Vec<C> {
+        self.0.clone()
+    }
+    fn runs(&self) -> Vec<(usize, &[C])> {
+        vec![(0, &self.0[..])]
+    }
+    fn as_slice(&self) -> Option<&[C]> {
+        Some(&self.0)
+    }
+    fn box_clone(&self) -> Box<dyn Memory<C>> {
+        Box::new(self.clone())
+    }
+}
+
+const PAGE_BITS: usize = 10;
+pub const PAGE_SIZE: usize = 1 << PAGE_BITS;
+// Pages below this are indexed directly, the rest is hashed.
+const LOW_PAGES: usize = 1 << 16;
+
+type Page<C> = Arc<[C; PAGE_SIZE]>;
+
+fn new_page<C: Cell>() -> Page<C> {
+    Arc::new(std::array::from_fn(|_| C::default()))
+}
+
+/// Fixed size pages that are allocated on the first write, for programs that scatter
+/// writes over a huge address range. Clones share pages until one of them writes to it.
+#[derive(Debug, Clone, Default)]
+pub struct Paged<C = isize> {
+    low: Vec<Option<Page<C>>>,
+    high: HashMap<usize, Page<C>>,
+    len: usize,
+}
+
+impl<C: Cell> Paged<C> {
+    fn pages_iter(&self) -> impl Iterator<Item = (usize, &Page<C>)> {
+        self.low
+            .iter()
+            .enumerate()
+            .filter_map(|(n, p)| p.as_ref().map(|p| (n, p)))
+            .chain(self.high.iter().map(|(&n, p)| (n, p)))
+    }
+    fn page(&self, n: usize) -> Option<&Page<C>> {
+        if n < LOW_PAGES {
+            self.low.get(n)?.as_ref()
+        } else {
+            self.high.get(&n)
+        }
+    }
+    fn page_mut(&mut self, n: usize) -> &mut [C; PAGE_SIZE] {
+        let page = if n < LOW_PAGES {
+            if n >= self.low.len() {
+                self.low.resize(n + 1, None);
+            }
+            self.low[n].get_or_insert_with(new_page)
+        } else {
+            self.high.entry(n).or_insert_with(new_page)
+        };
+        Arc::make_mut(page)
+    }
+    /// Number of allocated pages.
+    pub fn pages(&self) -> usize {
+        self.pages_iter().count()
+    }
+    /// Number of pages not shared with any clone.
+    pub fn private_pages(&self) -> usize {
+        self.pages_iter()
+            .filter(|(_, p)| Arc::strong_count(p) == 1)
+            .count()
+    }
+}
+
+impl<C: Cell> From<Vec<C>> for Paged<C> {
+    fn from(mem: Vec<C>) -> Self {
+        let mut paged = Paged::default();
+        for (n, cells) in mem.chunks(PAGE_SIZE).enumerate() {
+            paged.page_mut(n)[..cells.len()].clone_from_slice(cells);
+        }
+        paged.len = mem.len();
+        paged
+    }
+}
+
+impl<C: Cell> Memory<C> for Paged<C> {
+    fn get(&self, addr: usize) -> C {
+        self.page(addr >> PAGE_BITS)
+            .map_or_else(C::default, |p| p[addr % PAGE_SIZE].clone())
+    }
+    fn get_mut(&mut self, addr: usize) -> &mut C {
+        self.len = self.len.max(addr + 1);
+        &mut self.page_mut(addr >> PAGE_BITS)[addr % PAGE_SIZE]
+    }
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn to_vec(&self) -> Vec<C> {
+        let mut mem = vec![C::default(); self.len];
+        for (n, p) in self.pages_iter() {
+            let start = n << PAGE_BITS;
+            let end = (start + PAGE_SIZE).min(self.len);
+            mem[start..end].clone_from_slice(&p[..end - start]);
+        }
+        mem
+    }
+    fn runs(&self) -> Vec<(usize, &[C])> {
+        let mut pages: Vec<_> = self.pages_iter().collect();
+        pages.sort_unstable_by_key(|&(n, _)| n);
+        pages
+            .into_iter()
+            .map(|(n, p)| {
+                let start = n << PAGE_BITS;
+                (start, &p[..PAGE_SIZE.min(self.len - start)])
+            })
+            .collect()
+    }
+    fn box_clone(&self) -> Box<dyn Memory<C>> {
+        Box::new(self.clone())
+    }
+    fn is_cow(&self) -> bool {
+        true
+    }
+}
+
+// Backends that share pages come back as `Paged`, everything else as `Dense`.
+fn empty<C: Cell>(paged: bool) -> Box<dyn Memory<C>> {
+    if paged {
+        Box::new(Paged::default())
+    } else {
+        Box::new(Dense::default())
+    }
+}
+
+/// Copies the allocated cells of `mem` through `f` into the same kind of backend.
+pub(super) fn map_memory<C: Cell, D: Cell>(
+    mem: &dyn Memory<C>,
+    f: impl Fn(&C) -> D,
+) -> Box<dyn Memory<D>> {
+    let mut out = empty(mem.is_cow());
+    for (start, cells) in mem.runs() {
+        for (i, v) in cells.iter().enumerate() {
+            *out.get_mut(start + i) = f(v);
+        }
+    }
+    out
+}
+
+/// Backend kind followed by the allocated runs, as used by snapshots and traces.
+pub(super) fn write_memory<W: Write, C: Cell>(w: &mut W, mem: &dyn Memory<C>) -> io::Result<()> {
+    let runs = mem.runs();
+    w.write_all(&[mem.is_cow() as u8])?;
+    write_uvar(w, runs.len() as u64)?;
+    for (start, cells) in runs {
+        write_uvar(w, start as u64)?;
+        write_uvar(w, cells.len() as u64)?;
+        for v in cells {
+            v.write_var(w)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads what `write_memory` wrote. Runs of dense memory must end within `ADDRESS_LIMIT`.
+pub(super) fn read_memory<R: Read, C: Cell>(r: &mut R) -> io::Result<Box<dyn Memory<C>>> {
+    let mut kind = [0];
+    r.read_exact(&mut kind)?;
+    if kind[0] > 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown memory backend {}", kind[0]),
+        ));
+    }
+    let paged = kind[0] == 1;
+    let mut mem = empty(paged);
+    for _ in 0..read_usize(r)? {
+        let start = read_usize(r)?;
+        let len = read_usize(r)?;
+        // Dense memory allocates everything up to the end of a run, paged memory only the
+        // pages that values are actually read for.
+        let bound = if paged { usize::MAX } else { ADDRESS_LIMIT };
+        if start.checked_add(len).is_none_or(|end| end > bound) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "run out of range",
+            ));
+        }
+        for i in 0..len {
+            *mem.get_mut(start + i) = C::read_var(r)?;
+        }
+    }
+    Ok(mem)
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Moves the memory onto `Paged` unless it already shares pages, so forks of the
+    /// machine are cheap.
+    pub fn into_paged(mut self) -> Self {
+        if !self.mem.is_cow() {
+            self.mem = Box::new(Paged::from(self.mem.to_vec()));
+        }
+        self
+    }
+    /// Copy of the machine that shares memory pages with this one until either writes
+    /// them. Memory that can not be shared is copied into `Paged` for the fork alone, use
+    /// `into_paged` first to fork such a machine repeatedly. Watchpoints and the budget
+    /// are copied, traces and profiles are not.
+    pub fn fork(&self) -> Intcode<C> {
+        let mem = if self.mem.is_cow() {
+            self.mem.clone()
+        } else {
+            Box::new(Paged::from(self.mem.to_vec()))
+        };
+        Intcode {
+            mem,
+            pc: self.pc,
+            base: self.base,
+            limit: self.limit,
+            wrap: self.wrap,
+            watch: self.watch.clone(),
+            tracer: None,
+            budget: self.budget.clone(),
+            profile: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::State;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const QUINE: &str = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+
+    #[test]
+    fn backends() -> Result<(), Box<dyn Error>> {
+        let code: Vec<isize> = QUINE
+            .split(',')
+            .map(|v| v.parse())
+            .collect::<Result<_, _>>()?;
+        let mut dense = Intcode::from(code.clone());
+        let mut paged = Intcode::with_memory(Paged::from(code.clone()));
+        let out = dense.run_input(&[])?;
+        assert_eq!(&code, out.out());
+        assert_eq!(out, paged.run_input(&[])?);
+        assert_eq!(dense.memory(), paged.memory());
+        assert_eq!(102, paged.memory().len());
+        Ok(())
+    }
+    #[test]
+    fn sparse() -> Result<(), Box<dyn Error>> {
+        // Copy the cell at 5 * 10^11 to 10^12, then output both and an untouched cell.
+        let far = "109,500000000000,21201,0,0,500000000000,204,500000000000,204,0,4,123456789,99";
+        let mut mem = Paged::from(Intcode::from_str(far)?.memory().to_vec());
+        *mem.get_mut(500_000_000_000) = 42;
+        let mut ic = Intcode::with_memory(mem);
+        ic.set_address_limit(usize::MAX);
+        assert_eq!(vec![42, 42, 0], ic.run_input(&[])?.out().clone());
+        assert_eq!(42, ic.peek(1_000_000_000_000));
+        assert_eq!(1_000_000_000_001, ic.memory_len());
+
+        let mut paged: Paged = Paged::default();
+        assert_eq!(0, paged.get(7));
+        assert!(paged.is_empty());
+        *paged.get_mut(PAGE_SIZE + 3) = 5;
+        assert_eq!(1, paged.pages());
+        assert_eq!(PAGE_SIZE + 4, paged.len());
+        assert_eq!(5, paged.to_vec()[PAGE_SIZE + 3]);
+        *paged.get_mut(LOW_PAGES * PAGE_SIZE) = 6;
+        *paged.get_mut(1) = 7;
+        let runs = paged.runs();
+        let starts: Vec<_> = runs.iter().map(|&(start, _)| start).collect();
+        assert_eq!(vec![0, PAGE_SIZE, LOW_PAGES * PAGE_SIZE], starts);
+        assert_eq!(&[6], runs[2].1);
+        Ok(())
+    }
+    #[test]
+    fn fork() -> Result<(), Box<dyn Error>> {
+        // Stores its input at 2000 and outputs the cell at 10.
+        let dense = Intcode::from_str("3,2000,4,10,99,0,0,0,0,0,7")?;
+        let mut copy = dense.fork();
+        assert!(!dense.mem.is_cow() && copy.mem.is_cow());
+        assert_eq!(State::Halt(vec![7], 3), copy.run_input(&[1])?);
+        assert_eq!(0, dense.peek(2000));
+
+        let mut ic = dense.into_paged();
+        ic.start_trace();
+        let mut a = ic.fork();
+        let mut b = ic.fork();
+        assert!(a.trace().is_none());
+        assert_eq!(State::Halt(vec![7], 3), a.run_input(&[1])?);
+        *b.mem(10) = 8;
+        assert_eq!(State::Halt(vec![8], 3), b.run_input(&[2])?);
+        assert_eq!((1, 2, 0), (a.peek(2000), b.peek(2000), ic.peek(2000)));
+        assert_eq!(7, ic.peek(10));
+
+        let mut mem: Paged = Paged::from(vec![0; 3 * PAGE_SIZE]);
+        *mem.get_mut(5 * PAGE_SIZE) = 1;
+        let fork = mem.clone();
+        assert_eq!(0, mem.private_pages());
+        *mem.get_mut(PAGE_SIZE) = 1;
+        *mem.get_mut(LOW_PAGES * PAGE_SIZE) = 1;
+        assert_eq!((5, 2), (mem.pages(), mem.private_pages()));
+        assert_eq!((4, 1), (fork.pages(), fork.private_pages()));
+        assert_eq!(0, fork.get(PAGE_SIZE));
+        Ok(())
+    }
+    #[test]
+    fn corrupt() -> io::Result<()> {
+        // One run of a single cell starting at `ADDRESS_LIMIT`.
+        let run = |paged: u8| -> io::Result<Vec<u8>> {
+            let mut buf = vec![paged];
+            write_uvar(&mut buf, 1)?;
+            write_uvar(&mut buf, ADDRESS_LIMIT as u64)?;
+            write_uvar(&mut buf, 1)?;
+            7isize.write_var(&mut buf)?;
+            Ok(buf)
+        };
+        let err = read_memory::<_, isize>(&mut &run(0)?[..]).err();
+        assert_eq!(Some(io::ErrorKind::InvalidData), err.map(|e| e.kind()));
+        let mem = read_memory::<_, isize>(&mut &run(1)?[..])?;
+        assert_eq!(7, mem.get(ADDRESS_LIMIT));
+        Ok(())
+    }
+}
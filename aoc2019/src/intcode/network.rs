@@ -0,0 +1,323 @@
+use super::sched::{MachineId, Outcome, SchedError, Scheduler, Status};
+use super::{Intcode, WatchHit};
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+
+/// Address of the NAT, packets sent to it are kept until the network goes idle.
+pub const NAT: isize = 255;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Packet {
+    pub dest: isize,
+    pub x: isize,
+    pub y: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A NIC sent a packet. Packets to unknown addresses are dropped.
+    Sent(usize, Packet),
+    /// The network was idle and the NAT sent its last packet to address 0.
+    Nat(Packet),
+    /// A `WatchAction::Stop` watchpoint of the NIC triggered.
+    Watch(usize, WatchHit),
+    /// The budget or deadline of the NIC or the network ran out before the next
+    /// instruction of the NIC.
+    Budget(usize),
+}
+
+/// Machines that talk to each other with `(dest, x, y)` packets.
+///
+/// The network runs in rounds. In every round each NIC, in address order, gets its whole
+/// queue or a single -1 if the queue is empty, and runs until it waits for input again.
+/// Packets are delivered as soon as they are sent. A round is idle if all queues were
+/// empty at its start and no packet was sent; after `idle_rounds` consecutive idle rounds
+/// the NAT sends its last packet to address 0.
+pub struct Network {
+    sched: Scheduler,
+    taps: Vec<Receiver<isize>>,
+    out: Vec<Vec<isize>>,
+    queued: Vec<bool>,
+    nat: Option<Packet>,
+    idle: usize,
+    idle_rounds: usize,
+    events: VecDeque<Event>,
+    // Position in an interrupted round: the next NIC to run, whether it already got its
+    // input, and whether the round is still idle.
+    next: MachineId,
+    resume: bool,
+    round_idle: bool,
+}
+
+impl Network {
+    /// Creates `size` copies of `ic`, each gets its address as the first input.
+    pub fn new(ic: &Intcode, size: usize) -> Self {
+        let mut sched = Scheduler::new();
+        let taps = (0..size)
+            .map(|addr| {
+                let id = sched.add(ic.clone());
+                sched.send(id, addr as isize);
+                sched.tap(id)
+            })
+            .collect();
+        Network {
+            sched,
+            taps,
+            out: vec![Vec::new(); size],
+            queued: vec![true; size],
+            nat: None,
+            idle: 0,
+            idle_rounds: 2,
+            events: VecDeque::new(),
+            next: 0,
+            resume: false,
+            round_idle: false,
+        }
+    }
+    pub fn set_idle_rounds(&mut self, rounds: usize) {
+        self.idle_rounds = rounds.max(1);
+    }
+    /// Last packet the NAT received.
+    pub fn nat(&self) -> Option<Packet> {
+        self.nat
+    }
+    pub fn len(&self) -> usize {
+        self.sched.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.sched.is_empty()
+    }
+    /// Same as `Scheduler::set_budget`, for all NICs together.
+    pub fn set_budget(&mut self, steps: Option<u64>) {
+        self.sched.set_budget(steps);
+    }
+    pub fn budget(&self) -> Option<u64> {
+        self.sched.budget()
+    }
+    /// Same as `Scheduler::set_deadline`.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.sched.set_deadline(deadline);
+    }
+    pub fn machine(&self, addr: usize) -> &Intcode {
+        self.sched.machine(addr)
+    }
+    pub fn machine_mut(&mut self, addr: usize) -> &mut Intcode {
+        self.sched.machine_mut(addr)
+    }
+    fn halted(&self, addr: usize) -> bool {
+        matches!(self.sched.status(addr), Status::Halted(_))
+    }
+    fn deliver(&mut self, packet: Packet) {
+        if let Some(queued) = self.queued.get_mut(packet.dest as usize) {
+            self.sched.send(packet.dest as usize, packet.x);
+            self.sched.send(packet.dest as usize, packet.y);
+            *queued = true;
+        }
+    }
+    // Sends the complete packets `src` output so far, returns whether there were any.
+    fn route(&mut self, src: usize) -> bool {
+        self.out[src].extend(self.taps[src].try_iter());
+        let full = self.out[src].len() / 3 * 3;
+        let packets: Vec<_> = self.out[src]
+            .drain(..full)
+            .collect::<Vec<_>>()
+            .chunks(3)
+            .map(|p| Packet {
+                dest: p[0],
+                x: p[1],
+                y: p[2],
+            })
+            .collect();
+        for &packet in &packets {
+            if packet.dest == NAT {
+                self.nat = Some(packet);
+            } else {
+                self.deliver(packet);
+            }
+            self.events.push_back(Event::Sent(src, packet));
+        }
+        !packets.is_empty()
+    }
+    /// Runs one round, returns whether it was idle. A watchpoint or budget event ends the
+    /// round early with `false`, the next call continues it.
+    pub fn round(&mut self) -> Result<bool, SchedError> {
+        if self.next == 0 && !self.resume {
+            self.round_idle = (0..self.len()).all(|a| self.halted(a) || !self.queued[a]);
+        }
+        while self.next < self.len() {
+            let addr = self.next;
+            if !self.halted(addr) {
+                if !self.resume && !self.queued[addr] {
+                    self.sched.send(addr, -1);
+                }
+                self.queued[addr] = false;
+                self.resume = false;
+                let stop = self.sched.run_machine(addr)?;
+                self.round_idle &= !self.route(addr);
+                if let Some(stop) = stop {
+                    self.resume = true;
+                    self.events.push_back(match stop {
+                        Outcome::Watch(_, hit) => Event::Watch(addr, hit),
+                        _ => Event::Budget(addr),
+                    });
+                    return Ok(false);
+                }
+            }
+            self.next += 1;
+        }
+        self.next = 0;
+
+        let idle = self.round_idle;
+        self.idle = if idle { self.idle + 1 } else { 0 };
+        if self.idle >= self.idle_rounds {
+            if let Some(nat) = self.nat {
+                let packet = Packet { dest: 0, ..nat };
+                self.deliver(packet);
+                self.events.push_back(Event::Nat(packet));
+                self.idle = 0;
+            }
+        }
+        Ok(idle)
+    }
+    /// Runs rounds until something happens. Returns `None` once every NIC halted or the
+    /// network is idle and the NAT has nothing to send.
+    pub fn next_event(&mut self) -> Result<Option<Event>, SchedError> {
+        while self.events.is_empty() {
+            if (0..self.len()).all(|a| self.halted(a)) {
+                return Ok(None);
+            }
+            if self.round()? && self.idle >= self.idle_rounds {
+                return Ok(None);
+            }
+        }
+        Ok(self.events.pop_front())
+    }
+}
+
+impl Intcode {
+    pub fn network(&self, size: usize) -> Network {
+        Network::new(self, size)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{Access, WatchAction};
+    use std::error::Error;
+
+    // Sends (255, addr, 100 + addr) after boot, then answers every packet (x, y) with
+    // (255, x, y + 1).
+    const NIC: &str = "
+            in -> [addr]
+            out #255
+            out [addr]
+            add [addr], #100 -> [tmp]
+            out [tmp]
+        loop:
+            in -> [x]
+            eq [x], #-1 -> [tmp]
+            jnz [tmp], #loop
+            in -> [y]
+            add [y], #1 -> [y]
+            out #255
+            out [x]
+            out [y]
+            jnz #1, #loop
+        addr: data 0
+        x: data 0
+        y: data 0
+        tmp: data 0
+    ";
+
+    #[test]
+    fn nat() -> Result<(), Box<dyn Error>> {
+        let mut net = Intcode::from_asm(NIC)?.network(3);
+        for addr in 0..3 {
+            let packet = Packet {
+                dest: NAT,
+                x: addr as isize,
+                y: 100 + addr as isize,
+            };
+            assert_eq!(Some(Event::Sent(addr, packet)), net.next_event()?);
+        }
+        for y in 102..105 {
+            assert_eq!(
+                Some(Event::Nat(Packet { dest: 0, x: 2, y })),
+                net.next_event()?
+            );
+            let packet = Packet {
+                dest: NAT,
+                x: 2,
+                y: y + 1,
+            };
+            assert_eq!(Some(Event::Sent(0, packet)), net.next_event()?);
+        }
+        Ok(())
+    }
+    #[test]
+    fn idle() -> Result<(), Box<dyn Error>> {
+        let mut net = Intcode::from_asm("in -> [0]\nloop: in -> [0]\njnz #1, #loop")?.network(2);
+        net.set_idle_rounds(1);
+        assert!(!net.round()?);
+        assert!(net.round()?);
+        assert_eq!(None, net.next_event()?);
+
+        let mut net = Intcode::from_asm("in -> [0]\nout #7\nout #1\nout #2\nhalt")?.network(2);
+        assert_eq!(
+            Some(Event::Sent(
+                0,
+                Packet {
+                    dest: 7,
+                    x: 1,
+                    y: 2
+                }
+            )),
+            net.next_event()?
+        );
+        assert!(net.next_event()?.is_some());
+        assert_eq!(None, net.next_event()?);
+        Ok(())
+    }
+    #[test]
+    fn stops() -> Result<(), Box<dyn Error>> {
+        let code = "in -> [9]\nout #1\nout #2\nout #3\nhalt";
+        let sent = Event::Sent(
+            0,
+            Packet {
+                dest: 1,
+                x: 2,
+                y: 3,
+            },
+        );
+        let mut ic = Intcode::from_asm(code)?;
+        ic.watch(9, Access::Write, WatchAction::Stop);
+        let mut net = ic.network(2);
+        let hit = |new| WatchHit {
+            pc: 0,
+            addr: 9,
+            access: Access::Write,
+            old: 0,
+            new,
+        };
+        assert_eq!(Some(Event::Watch(0, hit(0))), net.next_event()?);
+        assert_eq!(Some(sent), net.next_event()?);
+        assert_eq!(Some(Event::Watch(1, hit(1))), net.next_event()?);
+
+        let mut ic = Intcode::from_asm(code)?;
+        ic.set_budget(Some(2));
+        let mut net = ic.network(2);
+        assert_eq!(Some(Event::Budget(0)), net.next_event()?);
+        assert_eq!(Some(Event::Budget(0)), net.next_event()?);
+        net.machine_mut(0).set_budget(None);
+        assert_eq!(Some(sent), net.next_event()?);
+        assert_eq!(Some(Event::Budget(1)), net.next_event()?);
+
+        let mut net = Intcode::from_asm(NIC)?.network(2);
+        net.set_budget(Some(6));
+        assert!(matches!(net.next_event()?, Some(Event::Sent(0, _))));
+        assert_eq!(Some(Event::Budget(1)), net.next_event()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,148 @@
+use super::disasm::decode_at;
+use super::wide::Cell;
+use super::{Intcode, Opcode};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Execution counts per pc and per opcode, and the largest memory size seen.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    pcs: HashMap<isize, u64>,
+    ops: HashMap<Opcode, u64>,
+    steps: u64,
+    high_water: usize,
+}
+
+impl Profile {
+    pub(super) fn record(&mut self, pc: isize, instr: isize, mem_len: usize) {
+        *self.pcs.entry(pc).or_insert(0) += 1;
+        if let Some(op) = Opcode::from_code(instr % 100) {
+            *self.ops.entry(op).or_insert(0) += 1;
+        }
+        self.steps += 1;
+        self.high_water = self.high_water.max(mem_len);
+    }
+    /// Number of executed instructions.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+    pub fn count(&self, pc: isize) -> u64 {
+        self.pcs.get(&pc).cloned().unwrap_or(0)
+    }
+    pub fn op_count(&self, op: Opcode) -> u64 {
+        self.ops.get(&op).cloned().unwrap_or(0)
+    }
+    /// Largest memory size in cells.
+    pub fn high_water(&self) -> usize {
+        self.high_water
+    }
+    /// Executed pcs, most frequent first.
+    pub fn hot(&self) -> Vec<(isize, u64)> {
+        let mut hot: Vec<_> = self.pcs.iter().map(|(&pc, &n)| (pc, n)).collect();
+        hot.sort_by_key(|&(pc, n)| (std::cmp::Reverse(n), pc));
+        hot
+    }
+    /// Adds the counts of `other`, e.g. of a clone that ran separately.
+    pub fn merge(&mut self, other: &Profile) {
+        for (&pc, &n) in &other.pcs {
+            *self.pcs.entry(pc).or_insert(0) += n;
+        }
+        for (&op, &n) in &other.ops {
+            *self.ops.entry(op).or_insert(0) += n;
+        }
+        self.steps += other.steps;
+        self.high_water = self.high_water.max(other.high_water);
+    }
+    fn percent(&self, n: u64) -> f64 {
+        100.0 * n as f64 / self.steps.max(1) as f64
+    }
+    /// Opcode table and the `top` hottest instructions, disassembled from `mem`.
+    pub fn report(&self, mem: &[isize], top: usize) -> String {
+        let mut s = String::new();
+        writeln!(
+            s,
+            "steps: {}, memory high-water mark: {}",
+            self.steps, self.high_water
+        )
+        .unwrap();
+
+        let mut ops: Vec<_> = self.ops.iter().map(|(&op, &n)| (op, n)).collect();
+        ops.sort_by_key(|&(op, n)| (std::cmp::Reverse(n), op.code()));
+        writeln!(s, "\n{:6} {:>12} {:>6}", "opcode", "count", "%").unwrap();
+        for (op, n) in ops {
+            writeln!(s, "{:6} {:>12} {:>6.2}", op.mnemonic(), n, self.percent(n)).unwrap();
+        }
+
+        writeln!(s, "\n{:>12} {:>6} {:>6}  instruction", "count", "%", "pc").unwrap();
+        for (pc, n) in self.hot().into_iter().take(top) {
+            let line = decode_at(mem, pc as usize).map_or_else(
+                || format!("data {}", mem.get(pc as usize).cloned().unwrap_or(0)),
+                |l| l.to_string(),
+            );
+            writeln!(s, "{:>12} {:>6.2} {:>6}  {}", n, self.percent(n), pc, line).unwrap();
+        }
+        s
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Starts counting executed instructions, discarding any previous profile.
+    pub fn start_profile(&mut self) {
+        self.profile = Some(Box::new(Profile {
+            high_water: self.mem.len(),
+            ..Default::default()
+        }));
+    }
+    pub fn stop_profile(&mut self) -> Option<Profile> {
+        self.profile.take().map(|p| *p)
+    }
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn counts() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_asm(
+            "
+                add #3, #0 -> [n]
+            loop:
+                add [n], #-1 -> [n]
+                out [n]
+                jnz [n], #loop
+                add #1, #1 -> [100]
+                halt
+            n:  data 0
+            ",
+        )?;
+        ic.start_profile();
+        assert_eq!(vec![2, 1, 0], ic.run_input(&[])?.out().clone());
+        let profile = ic.stop_profile().unwrap();
+        assert!(ic.profile().is_none());
+        assert_eq!(12, profile.steps());
+        assert_eq!(3, profile.count(4));
+        assert_eq!(0, profile.count(5));
+        assert_eq!(5, profile.op_count(Opcode::Add));
+        assert_eq!(1, profile.op_count(Opcode::Halt));
+        assert_eq!(101, profile.high_water());
+        assert_eq!((4, 3), profile.hot()[0]);
+
+        let mut merged = profile.clone();
+        merged.merge(&profile);
+        assert_eq!(24, merged.steps());
+        assert_eq!(6, merged.count(4));
+
+        let report = profile.report(&ic.memory(), 2);
+        assert!(report.starts_with("steps: 12, memory high-water mark: 101\n"));
+        assert!(report.contains("\nadd               5  41.67\n"));
+        assert!(report.contains("           3  25.00      4  add [18], #-1 -> [18]"));
+        assert!(report.contains("           3  25.00      8  out [18]"));
+        assert!(!report.contains("jnz [18]"));
+        Ok(())
+    }
+}
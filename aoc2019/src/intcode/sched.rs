@@ -0,0 +1,318 @@
+use super::budget::Budget;
+use super::{InstrState, Intcode, IntcodeError, IntcodeInput, WatchHit};
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::time::{Duration, Instant};
+
+pub type MachineId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ready,
+    Waiting,
+    Halted(isize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every machine halted.
+    Halted,
+    /// No machine could execute a single instruction, all live machines wait for input.
+    Deadlock,
+    /// A `WatchAction::Stop` watchpoint of the machine triggered.
+    Watch(MachineId, WatchHit),
+    /// The budget or deadline of the machine, or the one of the whole scheduler, ran out
+    /// before the next instruction of the machine.
+    Budget(MachineId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedError {
+    pub machine: MachineId,
+    pub error: IntcodeError,
+}
+
+impl fmt::Display for SchedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "machine {}: {}", self.machine, self.error)
+    }
+}
+
+impl Error for SchedError {}
+
+struct Machine {
+    ic: Intcode,
+    input: Receiver<isize>,
+    tx: Sender<isize>,
+    outputs: Vec<Sender<isize>>,
+    status: Status,
+}
+
+/// Runs a set of machines whose inputs and outputs are connected by channels, switching
+/// to the next machine when one waits for input or used up its time slice.
+pub struct Scheduler {
+    machines: Vec<Machine>,
+    slice: usize,
+    budget: Budget,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Scheduler {
+            machines: Vec::new(),
+            slice: 10_000,
+            budget: Budget::default(),
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Maximum number of instructions a machine executes before the next one gets its turn.
+    pub fn set_slice(&mut self, slice: usize) {
+        self.slice = slice.max(1);
+    }
+    /// Limits the number of instructions all machines together may execute from now on,
+    /// on top of their own budgets. `None` removes the limit.
+    pub fn set_budget(&mut self, steps: Option<u64>) {
+        self.budget.set_steps(steps);
+    }
+    pub fn budget(&self) -> Option<u64> {
+        self.budget.steps()
+    }
+    /// Stops every machine once `deadline` passed, including in later runs until the
+    /// deadline is changed.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.budget.set_deadline(deadline);
+    }
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.set_deadline(Some(Instant::now() + timeout));
+    }
+    pub fn add(&mut self, ic: Intcode) -> MachineId {
+        let (tx, input) = channel();
+        self.machines.push(Machine {
+            ic,
+            input,
+            tx,
+            outputs: Vec::new(),
+            status: Status::Ready,
+        });
+        self.machines.len() - 1
+    }
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+    /// Feeds every output of `from` into the input of `to`.
+    pub fn connect(&mut self, from: MachineId, to: MachineId) {
+        let tx = self.sender(to);
+        self.machines[from].outputs.push(tx);
+    }
+    /// Sender for the input channel of `id`.
+    pub fn sender(&self, id: MachineId) -> Sender<isize> {
+        self.machines[id].tx.clone()
+    }
+    pub fn send(&self, id: MachineId, v: isize) {
+        self.machines[id].tx.send(v).ok();
+    }
+    /// Receiver for a copy of everything `id` outputs from now on.
+    pub fn tap(&mut self, id: MachineId) -> Receiver<isize> {
+        let (tx, rx) = channel();
+        self.machines[id].outputs.push(tx);
+        rx
+    }
+    pub fn machine(&self, id: MachineId) -> &Intcode {
+        &self.machines[id].ic
+    }
+    pub fn machine_mut(&mut self, id: MachineId) -> &mut Intcode {
+        &mut self.machines[id].ic
+    }
+    pub fn status(&self, id: MachineId) -> Status {
+        self.machines[id].status
+    }
+    pub fn halt_codes(&self) -> Vec<Option<isize>> {
+        self.machines
+            .iter()
+            .map(|m| match m.status {
+                Status::Halted(h) => Some(h),
+                _ => None,
+            })
+            .collect()
+    }
+    pub fn run(&mut self) -> Result<Outcome, SchedError> {
+        loop {
+            let mut live = false;
+            let mut progress = false;
+            for id in 0..self.machines.len() {
+                if let Status::Halted(_) = self.machines[id].status {
+                    continue;
+                }
+                live = true;
+                let (ran, stop) = self.run_slice(id, self.slice)?;
+                progress |= ran;
+                if let Some(stop) = stop {
+                    return Ok(stop);
+                }
+            }
+            if !live {
+                return Ok(Outcome::Halted);
+            }
+            if !progress {
+                return Ok(Outcome::Deadlock);
+            }
+        }
+    }
+    /// Runs only `id` until it waits for input or halts. Returns the outcome if a
+    /// watchpoint or the budget stopped it first.
+    pub fn run_machine(&mut self, id: MachineId) -> Result<Option<Outcome>, SchedError> {
+        Ok(self.run_slice(id, usize::MAX)?.1)
+    }
+    // Executes up to `steps` instructions of `id`, returns whether it executed any and
+    // why it stopped early.
+    fn run_slice(
+        &mut self,
+        id: MachineId,
+        steps: usize,
+    ) -> Result<(bool, Option<Outcome>), SchedError> {
+        let m = &mut self.machines[id];
+        let mut progress = false;
+        if let Status::Halted(_) = m.status {
+            return Ok((progress, None));
+        }
+        self.budget.start();
+        m.ic.budget.start();
+        for _ in 0..steps {
+            if self.budget.exhausted() || m.ic.budget.exhausted() {
+                return Ok((progress, Some(Outcome::Budget(id))));
+            }
+            let input = &mut m.input;
+            let mut in_iter = std::iter::from_fn(|| input.input());
+            let state =
+                m.ic.step(&mut in_iter)
+                    .map_err(|error| SchedError { machine: id, error })?;
+            if let InstrState::Wait = state {
+                m.status = Status::Waiting;
+                break;
+            }
+            m.ic.budget.charge();
+            self.budget.charge();
+            progress = true;
+            m.status = Status::Ready;
+            match state {
+                InstrState::Output(o) => {
+                    for tx in &m.outputs {
+                        tx.send(o).ok();
+                    }
+                }
+                InstrState::Halt(h) => {
+                    m.status = Status::Halted(h);
+                    break;
+                }
+                _ => (),
+            }
+            if let Some(hit) = m.ic.take_watch_stop() {
+                return Ok((progress, Some(Outcome::Watch(id, hit))));
+            }
+        }
+        Ok((progress, None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn feedback() -> Result<(), Box<dyn Error>> {
+        let code =
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+        let mut sched = Scheduler::new();
+        let amps = [9, 8, 7, 6, 5]
+            .iter()
+            .map(|&phase| {
+                let id = sched.add(Intcode::from_str(code)?);
+                sched.send(id, phase);
+                Ok(id)
+            })
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+        for (&from, &to) in amps.iter().zip(amps.iter().cycle().skip(1)) {
+            sched.connect(from, to);
+        }
+        let thrust = sched.tap(amps[4]);
+        sched.send(amps[0], 0);
+        assert_eq!(Outcome::Halted, sched.run()?);
+        assert_eq!(Some(139_629_729), thrust.try_iter().last());
+        assert_eq!(vec![Some(3); 5], sched.halt_codes());
+        Ok(())
+    }
+    #[test]
+    fn deadlock() -> Result<(), Box<dyn Error>> {
+        let mut sched = Scheduler::new();
+        sched.set_slice(1);
+        let echo = sched.add(Intcode::from_str("3,7,4,7,1105,1,0")?);
+        let sink = sched.add(Intcode::from_str("3,5,1105,1,0")?);
+        let src = sched.add(Intcode::from_str("104,7,99")?);
+        sched.connect(src, echo);
+        sched.connect(echo, sink);
+        let out = sched.tap(echo);
+        assert_eq!(Outcome::Deadlock, sched.run()?);
+        assert_eq!(vec![7], out.try_iter().collect::<Vec<_>>());
+        assert_eq!(Status::Waiting, sched.status(echo));
+        assert_eq!(vec![None, None, Some(104)], sched.halt_codes());
+
+        sched.send(echo, 3);
+        assert_eq!(Outcome::Deadlock, sched.run()?);
+        assert_eq!(vec![3], out.try_iter().collect::<Vec<_>>());
+        Ok(())
+    }
+    #[test]
+    fn budget() -> Result<(), Box<dyn Error>> {
+        let mut sched = Scheduler::new();
+        let src = sched.add(Intcode::from_str("104,1,104,2,99")?);
+        let mut spin = Intcode::from_str("3,9,1105,1,0")?;
+        spin.set_budget(Some(3));
+        let spin = sched.add(spin);
+        sched.connect(src, spin);
+        assert_eq!(Outcome::Budget(spin), sched.run()?);
+        assert_eq!(
+            (2, Some(0)),
+            (sched.machine(spin).pc(), sched.machine(spin).budget())
+        );
+        sched.machine_mut(spin).set_budget(None);
+        assert_eq!(Outcome::Deadlock, sched.run()?);
+        assert_eq!(2, sched.machine(spin).peek(9));
+
+        let mut sched = Scheduler::new();
+        sched.set_slice(10);
+        sched.add(Intcode::from_str("1105,1,0")?);
+        sched.add(Intcode::from_str("1105,1,0")?);
+        sched.set_budget(Some(25));
+        assert_eq!(Outcome::Budget(0), sched.run()?);
+        assert_eq!(Some(0), sched.budget());
+        sched.set_budget(None);
+        sched.set_timeout(Duration::from_millis(10));
+        assert!(matches!(sched.run()?, Outcome::Budget(_)));
+        assert!(matches!(sched.run()?, Outcome::Budget(_)));
+        Ok(())
+    }
+    #[test]
+    fn error() -> Result<(), Box<dyn Error>> {
+        let mut sched = Scheduler::new();
+        sched.add(Intcode::from_str("3,0,99")?);
+        sched.add(Intcode::from_str("1,0,0,0,42")?);
+        assert_eq!(
+            Err(SchedError {
+                machine: 1,
+                error: IntcodeError::InvalidOpcode { pc: 4, instr: 42 }
+            }),
+            sched.run()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use super::codec::{read_header, read_usize, read_var, write_header, write_uvar, write_var};
+use super::memory::{read_memory, write_memory};
+use super::wide::Cell;
+use super::Intcode;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"ICSN";
+const VERSION: u8 = 3;
+
+/// Persistable state of a paused machine: memory, pc, relative base, address limit,
+/// overflow behaviour and the input that was queued but not consumed yet. Only allocated memory is stored, and it is restored
+/// on the same kind of backend. Watchpoints and traces are not part of a snapshot.
+#[derive(Clone)]
+pub struct Snapshot<C: Cell = isize> {
+    pub ic: Intcode<C>,
+    pub input: Vec<C>,
+}
+
+fn write_values<W: Write, C: Cell>(w: &mut W, values: &[C]) -> io::Result<()> {
+    write_uvar(w, values.len() as u64)?;
+    for v in values {
+        v.write_var(w)?;
+    }
+    Ok(())
+}
+
+fn read_values<R: Read, C: Cell>(r: &mut R) -> io::Result<Vec<C>> {
+    (0..read_usize(r)?).map(|_| C::read_var(r)).collect()
+}
+
+impl<C: Cell> Snapshot<C> {
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write_header(&mut w, MAGIC, VERSION)?;
+        write_var(&mut w, self.ic.pc)?;
+        write_var(&mut w, self.ic.base)?;
+        write_uvar(&mut w, self.ic.limit as u64)?;
+        w.write_all(&[self.ic.wrap as u8])?;
+        write_memory(&mut w, &*self.ic.mem)?;
+        write_values(&mut w, &self.input)?;
+        w.flush()
+    }
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        read_header(&mut r, MAGIC, VERSION)?;
+        let pc = read_var(&mut r)?;
+        let base = read_var(&mut r)?;
+        let limit = read_usize(&mut r)?;
+        let mut wrap = [0];
+        r.read_exact(&mut wrap)?;
+        let mut ic = Intcode::from_box(read_memory(&mut r)?);
+        ic.pc = pc;
+        ic.base = base;
+        ic.limit = limit;
+        ic.wrap = wrap[0] != 0;
+        let input = read_values(&mut r)?;
+        Ok(Snapshot { ic, input })
+    }
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Snapshot::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    pub fn snapshot(&self, input: &[C]) -> Snapshot<C> {
+        let mut ic = Intcode::from(Vec::new());
+        ic.mem = self.mem.clone();
+        ic.pc = self.pc;
+        ic.base = self.base;
+        ic.limit = self.limit;
+        ic.wrap = self.wrap;
+        Snapshot {
+            ic,
+            input: input.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::memory::PAGE_SIZE;
+    use crate::intcode::{Paged, State, ADDRESS_LIMIT};
+    use std::error::Error;
+    use std::str::FromStr;
+
+    #[test]
+    fn resume() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_asm(
+            "
+                arb #100
+            loop:
+                in -> rel[+0]
+                in -> rel[+1]
+                mul rel[+0], rel[+1] -> rel[+2]
+                out rel[+2]
+                jnz #1, #loop
+            ",
+        )?;
+        assert_eq!(State::Wait(vec![6]), ic.run_input(&[2, 3])?);
+
+        let mut buf = Vec::new();
+        ic.snapshot(&[4]).write_to(&mut buf)?;
+        let Snapshot {
+            ic: mut restored,
+            input,
+        } = Snapshot::read_from(&buf[..])?;
+        assert_eq!(vec![4], input);
+        assert_eq!(ic.pc(), restored.pc());
+        assert_eq!(ic.base(), restored.base());
+        assert_eq!(ic.memory(), restored.memory());
+
+        let resumed = restored.run_input(&[&input[..], &[5]].concat())?;
+        assert_eq!(State::Wait(vec![20]), resumed);
+        assert_eq!(resumed, ic.run_input(&[4, 5])?);
+
+        buf[4] += 1;
+        assert!(Snapshot::<isize>::read_from(&buf[..]).is_err());
+        Ok(())
+    }
+    #[test]
+    fn sparse() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::with_memory(Paged::from(vec![3isize, 1 << 40, 99]));
+        ic.set_address_limit(usize::MAX);
+        ic.set_wrap(true);
+        assert_eq!(State::Halt(vec![], 3), ic.run_input(&[7])?);
+
+        let mut buf = Vec::new();
+        ic.snapshot(&[]).write_to(&mut buf)?;
+        assert!(buf.len() < 4 * PAGE_SIZE);
+        let restored = Snapshot::<isize>::read_from(&buf[..])?.ic;
+        assert!(restored.mem.is_cow());
+        assert_eq!(
+            (usize::MAX, true),
+            (restored.address_limit(), restored.wraps())
+        );
+        assert_eq!((1 << 40) + 1, restored.memory_len());
+        assert_eq!((7, 99), (restored.peek(1 << 40), restored.peek(2)));
+        Ok(())
+    }
+    #[test]
+    fn corrupt() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_str("3,20,99")?;
+        let mut buf = Vec::new();
+        ic.snapshot(&[]).write_to(&mut buf)?;
+        // The only run starts at 0, move it past the address limit.
+        let header = buf.len() - 9;
+        assert_eq!(&[0, 1, 0, 3], &buf[header..header + 4]);
+        let mut bad = buf[..header + 2].to_vec();
+        write_uvar(&mut bad, ADDRESS_LIMIT as u64)?;
+        bad.extend(&buf[header + 3..]);
+        let err = Snapshot::<isize>::read_from(&bad[..]).err();
+        assert_eq!(Some(io::ErrorKind::InvalidData), err.map(|e| e.kind()));
+        Ok(())
+    }
+}
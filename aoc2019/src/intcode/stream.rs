@@ -0,0 +1,135 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+
+/// Source of values for the input instruction. Returning `None` makes the machine wait.
+pub trait IntcodeInput<C = isize> {
+    fn input(&mut self) -> Option<C>;
+}
+
+/// Sink for values of the output instruction.
+pub trait IntcodeOutput<C = isize> {
+    fn output(&mut self, v: C);
+}
+
+impl<C, F: FnMut() -> Option<C>> IntcodeInput<C> for F {
+    fn input(&mut self) -> Option<C> {
+        self()
+    }
+}
+
+impl<C> IntcodeInput<C> for VecDeque<C> {
+    fn input(&mut self) -> Option<C> {
+        self.pop_front()
+    }
+}
+
+impl<C> IntcodeInput<C> for Receiver<C> {
+    fn input(&mut self) -> Option<C> {
+        self.try_recv().ok()
+    }
+}
+
+impl<C> IntcodeInput<C> for dyn Iterator<Item = C> + '_ {
+    fn input(&mut self) -> Option<C> {
+        self.next()
+    }
+}
+
+impl<C: Clone> IntcodeInput<C> for std::slice::Iter<'_, C> {
+    fn input(&mut self) -> Option<C> {
+        self.next().cloned()
+    }
+}
+
+impl<C> IntcodeInput<C> for std::vec::IntoIter<C> {
+    fn input(&mut self) -> Option<C> {
+        self.next()
+    }
+}
+
+impl<C, F: FnMut(C)> IntcodeOutput<C> for F {
+    fn output(&mut self, v: C) {
+        self(v)
+    }
+}
+
+impl<C> IntcodeOutput<C> for Vec<C> {
+    fn output(&mut self, v: C) {
+        self.push(v)
+    }
+}
+
+impl<C> IntcodeOutput<C> for VecDeque<C> {
+    fn output(&mut self, v: C) {
+        self.push_back(v)
+    }
+}
+
+// Output sent to a dropped receiver is discarded.
+impl<C> IntcodeOutput<C> for Sender<C> {
+    fn output(&mut self, v: C) {
+        self.send(v).ok();
+    }
+}
+
+impl<C> IntcodeOutput<C> for SyncSender<C> {
+    fn output(&mut self, v: C) {
+        self.send(v).ok();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::intcode::{Intcode, State};
+    use std::cell::Cell;
+    use std::collections::VecDeque;
+    use std::error::Error;
+    use std::str::FromStr;
+    use std::sync::mpsc::channel;
+
+    const DOUBLE: &str = "3,9,1002,9,2,9,4,9,1105,1,0";
+
+    #[test]
+    fn on_demand() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str(DOUBLE)?;
+        let last = Cell::new(1);
+        let mut out = Vec::new();
+        let mut input = || Some(last.get()).filter(|&v| v < 100);
+        let mut output = |v| {
+            last.set(v);
+            out.push(v)
+        };
+        assert_eq!(State::Wait(()), ic.run_with(&mut input, &mut output)?);
+        assert_eq!(vec![2, 4, 8, 16, 32, 64, 128], out);
+        Ok(())
+    }
+    #[test]
+    fn containers() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str(DOUBLE)?;
+        let mut queue: VecDeque<isize> = vec![1, 2].into();
+        let mut out = VecDeque::new();
+        ic.run_with(&mut queue, &mut out)?;
+        assert_eq!(vec![2, 4], Vec::from(out));
+
+        let mut out = Vec::new();
+        ic.run_with(&mut [3, 4].iter(), &mut out)?;
+        ic.run_with(&mut vec![5].into_iter(), &mut out)?;
+        ic.run_with(
+            &mut (6..8isize) as &mut dyn Iterator<Item = isize>,
+            &mut out,
+        )?;
+        assert_eq!(vec![6, 8, 10, 12, 14], out);
+        Ok(())
+    }
+    #[test]
+    fn channels() -> Result<(), Box<dyn Error>> {
+        let (in_tx, mut in_rx) = channel();
+        let (mut out_tx, out_rx) = channel();
+        let mut ic = Intcode::from_str(DOUBLE)?;
+        in_tx.send(21)?;
+        assert_eq!(State::Wait(()), ic.run_with(&mut in_rx, &mut out_tx)?);
+        assert_eq!(42, out_rx.try_recv()?);
+        assert!(out_rx.try_recv().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,609 @@
+use super::{Instr, Intcode, IntcodeError, Mode, Opcode, ADDRESS_LIMIT};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+/// Forking on a written address or jump target is given up beyond this many candidates.
+const MAX_FORK: i128 = 256;
+
+/// A cell in terms of the symbols of a `Symbolic` run.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(isize),
+    /// The symbol with this index.
+    Sym(usize),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    Lt(Rc<Expr>, Rc<Expr>),
+    Eq(Rc<Expr>, Rc<Expr>),
+    /// A read through a symbolic address, from memory as it was at the time. The memory is
+    /// shared with the path until either changes.
+    Load(Rc<Vec<Rc<Expr>>>, Rc<Expr>),
+}
+
+impl Expr {
+    pub fn constant(v: isize) -> Rc<Expr> {
+        Rc::new(Expr::Const(v))
+    }
+    pub fn value(&self) -> Option<isize> {
+        match *self {
+            Expr::Const(v) => Some(v),
+            _ => None,
+        }
+    }
+    pub fn sum(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match (a.value(), b.value()) {
+            (Some(x), Some(y)) if x.checked_add(y).is_some() => Expr::constant(x + y),
+            (Some(0), _) => b,
+            (_, Some(0)) => a,
+            _ => Rc::new(Expr::Add(a, b)),
+        }
+    }
+    pub fn product(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match (a.value(), b.value()) {
+            (Some(x), Some(y)) if x.checked_mul(y).is_some() => Expr::constant(x * y),
+            (Some(0), _) | (_, Some(0)) => Expr::constant(0),
+            (Some(1), _) => b,
+            (_, Some(1)) => a,
+            _ => Rc::new(Expr::Mul(a, b)),
+        }
+    }
+    pub fn less(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match (a.value(), b.value()) {
+            (Some(x), Some(y)) => Expr::constant((x < y) as isize),
+            _ => Rc::new(Expr::Lt(a, b)),
+        }
+    }
+    pub fn equal(a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        match (a.value(), b.value()) {
+            (Some(x), Some(y)) => Expr::constant((x == y) as isize),
+            _ if a == b => Expr::constant(1),
+            _ => Rc::new(Expr::Eq(a, b)),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(v) => write!(f, "{}", v),
+            Expr::Sym(n) => write!(f, "s{}", n),
+            Expr::Add(a, b) => write!(f, "({} + {})", a, b),
+            Expr::Mul(a, b) => write!(f, "({} * {})", a, b),
+            Expr::Lt(a, b) => write!(f, "({} < {})", a, b),
+            Expr::Eq(a, b) => write!(f, "({} == {})", a, b),
+            Expr::Load(_, addr) => write!(f, "mem[{}]", addr),
+        }
+    }
+}
+
+type Interval = (i128, i128);
+
+const ANY: Interval = (isize::MIN as i128, isize::MAX as i128);
+
+/// Interval arithmetic over the symbol domains. Exact once every domain is a single value.
+struct Bounds<'a> {
+    domains: &'a [(isize, isize)],
+    memo: HashMap<*const Expr, Interval>,
+}
+
+impl Bounds<'_> {
+    fn of(&mut self, e: &Expr) -> Interval {
+        if let Some(&b) = self.memo.get(&(e as *const _)) {
+            return b;
+        }
+        let b = match e {
+            Expr::Const(v) => (*v as i128, *v as i128),
+            Expr::Sym(n) => (self.domains[*n].0 as i128, self.domains[*n].1 as i128),
+            Expr::Add(a, b) => {
+                let (a, b) = (self.of(a), self.of(b));
+                (a.0.saturating_add(b.0), a.1.saturating_add(b.1))
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (self.of(a), self.of(b));
+                let p = [
+                    a.0.saturating_mul(b.0),
+                    a.0.saturating_mul(b.1),
+                    a.1.saturating_mul(b.0),
+                    a.1.saturating_mul(b.1),
+                ];
+                (*p.iter().min().unwrap(), *p.iter().max().unwrap())
+            }
+            Expr::Lt(a, b) => {
+                let (a, b) = (self.of(a), self.of(b));
+                match () {
+                    _ if a.1 < b.0 => (1, 1),
+                    _ if a.0 >= b.1 => (0, 0),
+                    _ => (0, 1),
+                }
+            }
+            Expr::Eq(a, b) => {
+                let (a, b) = (self.of(a), self.of(b));
+                match () {
+                    _ if a.0 == a.1 && a == b => (1, 1),
+                    _ if a.1 < b.0 || b.1 < a.0 => (0, 0),
+                    _ => (0, 1),
+                }
+            }
+            Expr::Load(mem, addr) => {
+                let (lo, hi) = self.of(addr);
+                let lo = lo.max(0);
+                if hi < lo || hi - lo > 4096 {
+                    ANY
+                } else {
+                    (lo..=hi)
+                        .map(|a| match mem.get(a as usize) {
+                            Some(e) => self.of(e),
+                            None => (0, 0),
+                        })
+                        .fold((i128::MAX, i128::MIN), |(l, h), (a, b)| {
+                            (l.min(a), h.max(b))
+                        })
+                }
+            }
+        };
+        self.memo.insert(e as *const _, b);
+        b
+    }
+}
+
+/// How a path ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    Halt,
+    /// Waiting for more input than symbols were provided for.
+    Wait,
+    /// Cut short by the step budget.
+    Budget,
+    /// A written address, jump target or opcode depends on too many values to fork.
+    Symbolic {
+        pc: isize,
+    },
+    Error(IntcodeError),
+}
+
+/// One way through the program, under its `constraints`.
+#[derive(Debug, Clone)]
+pub struct Path {
+    // Copied on write, so forks and loads share it.
+    mem: Rc<Vec<Rc<Expr>>>,
+    pc: isize,
+    base: isize,
+    inputs: usize,
+    /// Conditions that all have to be nonzero to take this path.
+    pub constraints: Vec<Rc<Expr>>,
+    pub outputs: Vec<Rc<Expr>>,
+    pub end: End,
+}
+
+impl Path {
+    pub fn peek(&self, addr: usize) -> Rc<Expr> {
+        self.mem
+            .get(addr)
+            .cloned()
+            .unwrap_or_else(|| Expr::constant(0))
+    }
+    pub fn pc(&self) -> isize {
+        self.pc
+    }
+    fn poke(&mut self, addr: usize, v: Rc<Expr>) {
+        let mem = Rc::make_mut(&mut self.mem);
+        if addr >= mem.len() {
+            mem.resize(addr + 1, Expr::constant(0));
+        }
+        mem[addr] = v;
+    }
+}
+
+enum Step {
+    Next,
+    Fork(Vec<Path>),
+    End(End),
+}
+
+/// Runs a program with symbolic cells and inputs, following both sides of every jump that
+/// depends on them, and solves for symbol values.
+pub struct Symbolic {
+    mem: Vec<Rc<Expr>>,
+    domains: Vec<(isize, isize)>,
+    inputs: Vec<usize>,
+    budget: u64,
+    limit: usize,
+}
+
+impl Symbolic {
+    pub fn new(mem: &[isize]) -> Self {
+        Symbolic {
+            mem: mem.iter().map(|&v| Expr::constant(v)).collect(),
+            domains: Vec::new(),
+            inputs: Vec::new(),
+            budget: 1_000_000,
+            limit: ADDRESS_LIMIT,
+        }
+    }
+    /// Steps all paths together may take.
+    pub fn set_budget(&mut self, steps: u64) {
+        self.budget = steps;
+    }
+    /// Same as `Intcode::set_address_limit`.
+    pub fn set_address_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+    fn new_symbol(&mut self, domain: RangeInclusive<isize>) -> usize {
+        self.domains.push((*domain.start(), *domain.end()));
+        self.domains.len() - 1
+    }
+    /// Replaces the cell at `addr` with a new symbol and returns its index.
+    pub fn symbol(&mut self, addr: usize, domain: RangeInclusive<isize>) -> usize {
+        let n = self.new_symbol(domain);
+        if addr >= self.mem.len() {
+            self.mem.resize(addr + 1, Expr::constant(0));
+        }
+        self.mem[addr] = Rc::new(Expr::Sym(n));
+        n
+    }
+    /// Adds a symbol for the next value read by opcode 3 and returns its index.
+    pub fn input(&mut self, domain: RangeInclusive<isize>) -> usize {
+        let n = self.new_symbol(domain);
+        self.inputs.push(n);
+        n
+    }
+    /// All paths from the start, depth first, taken branches before fallthroughs.
+    pub fn explore(&self) -> Vec<Path> {
+        let mut todo = vec![Path {
+            mem: Rc::new(self.mem.clone()),
+            pc: 0,
+            base: 0,
+            inputs: 0,
+            constraints: Vec::new(),
+            outputs: Vec::new(),
+            end: End::Budget,
+        }];
+        let mut done = Vec::new();
+        let mut steps = 0;
+        while let Some(mut path) = todo.pop() {
+            loop {
+                if steps == self.budget {
+                    done.push(path);
+                    break;
+                }
+                steps += 1;
+                match self.step(&mut path) {
+                    Step::Next => (),
+                    Step::Fork(paths) => {
+                        todo.extend(paths.into_iter().rev());
+                        break;
+                    }
+                    Step::End(end) => {
+                        path.end = end;
+                        done.push(path);
+                        break;
+                    }
+                }
+            }
+        }
+        done
+    }
+    /// Values for all symbols making every one of `constraints` nonzero.
+    pub fn solve(&self, constraints: &[Rc<Expr>]) -> Option<Vec<isize>> {
+        let mut domains = self.domains.clone();
+        if self.search(&mut domains, constraints) {
+            Some(domains.iter().map(|d| d.0).collect())
+        } else {
+            None
+        }
+    }
+    /// Solves the constraints of each finished path together with the `goal` built for it,
+    /// and returns the first solution.
+    pub fn find<F>(&self, goal: F) -> Option<Vec<isize>>
+    where
+        F: Fn(&Path) -> Option<Rc<Expr>>,
+    {
+        self.explore().iter().find_map(|path| {
+            let mut constraints = path.constraints.clone();
+            constraints.push(goal(path)?);
+            self.solve(&constraints)
+        })
+    }
+    /// Bisects the first domain that is not a single value until all constraints are known
+    /// to hold, pruning whenever one is known to fail.
+    fn search(&self, domains: &mut Vec<(isize, isize)>, constraints: &[Rc<Expr>]) -> bool {
+        let mut bounds = Bounds {
+            domains,
+            memo: HashMap::new(),
+        };
+        let mut settled = true;
+        for c in constraints {
+            match bounds.of(c) {
+                (0, 0) => return false,
+                (lo, hi) if lo <= 0 && hi >= 0 => settled = false,
+                _ => (),
+            }
+        }
+        if settled {
+            return true;
+        }
+        let n = match domains.iter().position(|d| d.0 != d.1) {
+            Some(n) => n,
+            None => return false,
+        };
+        let (lo, hi) = domains[n];
+        let mid = lo + (hi - lo) / 2;
+        for half in [(lo, mid), (mid + 1, hi)] {
+            domains[n] = half;
+            if self.search(domains, constraints) {
+                return true;
+            }
+        }
+        domains[n] = (lo, hi);
+        false
+    }
+    fn feasible(&self, constraints: &[Rc<Expr>]) -> bool {
+        self.search(&mut self.domains.clone(), constraints)
+    }
+    /// Continues `path` once for each value the cell at `addr` can take.
+    fn fork(&self, path: &Path, addr: usize) -> Step {
+        let e = path.peek(addr);
+        let mut bounds = Bounds {
+            domains: &self.domains,
+            memo: HashMap::new(),
+        };
+        let (lo, hi) = bounds.of(&e);
+        if hi - lo >= MAX_FORK {
+            return Step::End(End::Symbolic { pc: path.pc });
+        }
+        let paths = (lo..=hi)
+            .filter_map(|v| {
+                let mut path = path.clone();
+                let v = Expr::constant(v as isize);
+                path.constraints.push(Expr::equal(e.clone(), v.clone()));
+                path.poke(addr, v);
+                Some(path).filter(|p| self.feasible(&p.constraints))
+            })
+            .collect();
+        Step::Fork(paths)
+    }
+    fn addr(path: &Path, pc: usize, off: usize, mode: Mode) -> Rc<Expr> {
+        match mode {
+            Mode::Position => path.peek(pc + off),
+            Mode::Immediate => Expr::constant((pc + off) as isize),
+            Mode::Relative => Expr::sum(path.peek(pc + off), Expr::constant(path.base)),
+        }
+    }
+    fn check(&self, path: &Path, addr: isize) -> Result<usize, Step> {
+        let error = if addr < 0 {
+            IntcodeError::NegativeAddress {
+                pc: path.pc,
+                instr: path.peek(path.pc as usize).value().unwrap_or(0),
+                addr,
+            }
+        } else if addr as usize >= self.limit {
+            IntcodeError::AddressTooLarge { pc: path.pc, addr }
+        } else {
+            return Ok(addr as usize);
+        };
+        Err(Step::End(End::Error(error)))
+    }
+    fn get(&self, path: &Path, pc: usize, off: usize, mode: Mode) -> Result<Rc<Expr>, Step> {
+        let addr = Symbolic::addr(path, pc, off, mode);
+        match addr.value() {
+            Some(a) => Ok(path.peek(self.check(path, a)?)),
+            None => Ok(Rc::new(Expr::Load(path.mem.clone(), addr))),
+        }
+    }
+    fn concrete_addr(&self, path: &Path, pc: usize, off: usize, mode: Mode) -> Result<usize, Step> {
+        match Symbolic::addr(path, pc, off, mode).value() {
+            Some(a) => self.check(path, a),
+            None => Err(self.fork(path, pc + off)),
+        }
+    }
+    fn concrete(&self, path: &Path, pc: usize, off: usize, mode: Mode) -> Result<isize, Step> {
+        let addr = self.concrete_addr(path, pc, off, mode)?;
+        path.peek(addr).value().ok_or_else(|| self.fork(path, addr))
+    }
+    fn step(&self, path: &mut Path) -> Step {
+        match self.try_step(path) {
+            Ok(()) => Step::Next,
+            Err(step) => step,
+        }
+    }
+    fn try_step(&self, path: &mut Path) -> Result<(), Step> {
+        let error = |e| Step::End(End::Error(e));
+        if path.pc < 0 || path.pc > isize::MAX - 4 {
+            return Err(error(IntcodeError::InvalidPc { pc: path.pc }));
+        }
+        let pc = path.pc as usize;
+        let code = self.concrete(path, pc, 0, Mode::Immediate)?;
+        let Instr {
+            op,
+            modes: [p1, p2, p3],
+        } = Instr::decode(path.pc, code).map_err(error)?;
+        let overflow = || error(IntcodeError::Overflow { pc: path.pc });
+        match op {
+            Opcode::Add | Opcode::Mul | Opcode::Lt | Opcode::Eq => {
+                let (a, b) = (self.get(path, pc, 1, p1)?, self.get(path, pc, 2, p2)?);
+                let v = match (op, a.value(), b.value()) {
+                    (Opcode::Add, Some(x), Some(y)) if x.checked_add(y).is_none() => {
+                        return Err(overflow())
+                    }
+                    (Opcode::Mul, Some(x), Some(y)) if x.checked_mul(y).is_none() => {
+                        return Err(overflow())
+                    }
+                    (Opcode::Add, _, _) => Expr::sum(a, b),
+                    (Opcode::Mul, _, _) => Expr::product(a, b),
+                    (Opcode::Lt, _, _) => Expr::less(a, b),
+                    _ => Expr::equal(a, b),
+                };
+                let addr = self.concrete_addr(path, pc, 3, p3)?;
+                path.poke(addr, v);
+                path.pc += 4;
+            }
+            Opcode::In => {
+                let addr = self.concrete_addr(path, pc, 1, p1)?;
+                let n = *self.inputs.get(path.inputs).ok_or(Step::End(End::Wait))?;
+                let v = match self.domains[n] {
+                    (lo, hi) if lo == hi => Expr::constant(lo),
+                    _ => Rc::new(Expr::Sym(n)),
+                };
+                path.poke(addr, v);
+                path.inputs += 1;
+                path.pc += 2;
+            }
+            Opcode::Out => {
+                let v = self.get(path, pc, 1, p1)?;
+                path.outputs.push(v);
+                path.pc += 2;
+            }
+            Opcode::Jnz | Opcode::Jz => {
+                let c = self.get(path, pc, 1, p1)?;
+                let mut bounds = Bounds {
+                    domains: &self.domains,
+                    memo: HashMap::new(),
+                };
+                let (lo, hi) = bounds.of(&c);
+                let (can_zero, can_nonzero) = (lo <= 0 && hi >= 0, (lo, hi) != (0, 0));
+                let can_jump = if op == Opcode::Jz {
+                    can_zero
+                } else {
+                    can_nonzero
+                };
+                let target = if can_jump {
+                    self.concrete(path, pc, 2, p2)?
+                } else {
+                    0
+                };
+                let take = |path: &mut Path, zero: bool| {
+                    if zero == (op == Opcode::Jz) {
+                        path.pc = target;
+                    } else {
+                        path.pc += 3;
+                    }
+                };
+                if can_zero && can_nonzero {
+                    let mut paths = Vec::new();
+                    for (cond, zero) in [
+                        (c.clone(), false),
+                        (Expr::equal(c, Expr::constant(0)), true),
+                    ] {
+                        let mut path = path.clone();
+                        path.constraints.push(cond);
+                        if self.feasible(&path.constraints) {
+                            take(&mut path, zero);
+                            paths.push(path);
+                        }
+                    }
+                    return Err(Step::Fork(paths));
+                }
+                take(path, can_zero);
+            }
+            Opcode::Arb => {
+                let v = self.concrete(path, pc, 1, p1)?;
+                path.base = path.base.checked_add(v).ok_or_else(overflow)?;
+                path.pc += 2;
+            }
+            Opcode::Halt => return Err(Step::End(End::Halt)),
+        }
+        Ok(())
+    }
+}
+
+impl Intcode {
+    pub fn symbolic(&self) -> Symbolic {
+        let mut sym = Symbolic::new(&self.image());
+        sym.limit = self.limit;
+        sym
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn branches() -> Result<(), Box<dyn Error>> {
+        // Outputs 1 only if 3 * x + y == 100 and x < y.
+        let ic = Intcode::from_asm(
+            "
+                    in -> [x]
+                    in -> [y]
+                    mul [x], #3 -> [t]
+                    add [t], [y] -> [t]
+                    eq [t], #100 -> [t]
+                    jz [t], #no
+                    lt [x], [y] -> [t]
+                    jz [t], #no
+                    out #1
+                    halt
+            no:     out #0
+                    halt
+            x:      data 0
+            y:      data 0
+            t:      data 0
+            ",
+        )?;
+        let mut sym = ic.symbolic();
+        sym.input(0..=1000);
+        sym.input(0..=1000);
+        let paths = sym.explore();
+        assert_eq!(3, paths.len());
+        assert!(paths.iter().all(|p| p.end == End::Halt));
+        assert_eq!(
+            "(((s0 * 3) + s1) == 100)",
+            paths[0].constraints[0].to_string()
+        );
+
+        let model = sym
+            .find(|p| Some(Expr::equal(p.outputs[0].clone(), Expr::constant(1))))
+            .unwrap();
+        assert_eq!(vec![1], *ic.clone().run_input(&model)?.out());
+        assert_eq!(
+            None,
+            sym.find(|p| Some(Expr::less(p.outputs[0].clone(), Expr::constant(0))))
+        );
+        Ok(())
+    }
+    #[test]
+    fn addresses() {
+        // Adds the cells the two symbols point at.
+        let mut sym = Symbolic::new(&[1, 0, 0, 0, 99, 20]);
+        let a = sym.symbol(1, 0..=9);
+        let b = sym.symbol(2, 0..=9);
+        let model = sym
+            .find(|p| Some(Expr::equal(p.peek(0), Expr::constant(119))))
+            .unwrap();
+        assert_eq!((4, 5), (model[a].min(model[b]), model[a].max(model[b])));
+        // Both loads share the memory they read from instead of copying it.
+        let sum = sym.explore()[0].peek(0);
+        match &*sum {
+            Expr::Add(x, y) => match (&**x, &**y) {
+                (Expr::Load(m, _), Expr::Load(n, _)) => assert!(Rc::ptr_eq(m, n)),
+                _ => panic!("not two loads: {}", sum),
+            },
+            _ => panic!("not a sum: {}", sum),
+        }
+
+        // Writes 7 to the address read from input.
+        let mut sym = Symbolic::new(&[3, 5, 1101, 7, 0, 0, 99]);
+        sym.input(0..=2);
+        let paths = sym.explore();
+        assert_eq!(3, paths.len());
+        assert_eq!(
+            Some(vec![0]),
+            sym.find(|p| Some(Expr::equal(p.peek(0), Expr::constant(7))))
+        );
+
+        sym.set_budget(1);
+        assert_eq!(End::Budget, sym.explore()[0].end);
+
+        let sym = Symbolic::new(&[1101, 1, 1, 1 << 40, 99]);
+        assert_eq!(
+            End::Error(IntcodeError::AddressTooLarge {
+                pc: 0,
+                addr: 1 << 40
+            }),
+            sym.explore()[0].end
+        );
+    }
+}
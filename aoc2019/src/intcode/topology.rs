@@ -0,0 +1,298 @@
+use super::asm::is_ident;
+use super::sched::{MachineId, Outcome, Scheduler};
+use super::Intcode;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologyError {
+    pub line: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for TopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl Error for TopologyError {}
+
+fn err<T>(line: usize, msg: String) -> Result<T, TopologyError> {
+    Err(TopologyError { line, msg })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Int(isize),
+    Param(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    name: String,
+    line: usize,
+    declared: bool,
+    input: Vec<Value>,
+}
+
+/// Named machines and the channels between them, parsed from a line based description:
+///
+/// ```text
+/// node a $0, 0    ; initial input of `a`, `$n` is the n-th parameter of `run`
+/// a -> b, c       ; outputs of `a` go to `b` and `c`
+/// chain b d e     ; b -> d, d -> e
+/// ring x y z      ; x -> y, y -> z, z -> x
+/// ```
+///
+/// Nodes are created on first mention, everything after a `;` is a comment. Every node
+/// runs its own copy of the same program.
+#[derive(Debug, Clone, Default)]
+pub struct Topology {
+    nodes: Vec<Node>,
+    edges: Vec<(usize, usize)>,
+}
+
+/// Result of running a topology: how the scheduler stopped, everything each node output
+/// and the halt codes.
+#[derive(Debug)]
+pub struct Report {
+    pub outcome: Outcome,
+    outputs: HashMap<String, Vec<isize>>,
+    halt_codes: HashMap<String, Option<isize>>,
+}
+
+impl Report {
+    pub fn output(&self, node: &str) -> &[isize] {
+        self.outputs.get(node).map_or(&[], |o| &o[..])
+    }
+    pub fn halt_code(&self, node: &str) -> Option<isize> {
+        self.halt_codes.get(node).cloned().flatten()
+    }
+}
+
+fn parse_value(line: usize, s: &str) -> Result<Value, TopologyError> {
+    let s = s.trim();
+    if let Some(n) = s.strip_prefix('$') {
+        return match n.parse() {
+            Ok(n) => Ok(Value::Param(n)),
+            Err(_) => err(line, format!("invalid parameter `{}`", s)),
+        };
+    }
+    match s.parse() {
+        Ok(v) => Ok(Value::Int(v)),
+        Err(_) => err(line, format!("invalid value `{}`", s)),
+    }
+}
+
+impl Topology {
+    fn node(&mut self, line: usize, name: &str) -> Result<usize, TopologyError> {
+        if !is_ident(name) {
+            return err(line, format!("invalid node name `{}`", name));
+        }
+        if let Some(id) = self.id(name) {
+            return Ok(id);
+        }
+        self.nodes.push(Node {
+            name: name.to_string(),
+            line,
+            declared: false,
+            input: Vec::new(),
+        });
+        Ok(self.nodes.len() - 1)
+    }
+    fn nodes(&mut self, line: usize, names: &[&str]) -> Result<Vec<usize>, TopologyError> {
+        if names.is_empty() {
+            return err(line, "expected at least one node".to_string());
+        }
+        names
+            .iter()
+            .map(|name| self.node(line, name.trim()))
+            .collect()
+    }
+    fn parse_line(&mut self, line: usize, text: &str) -> Result<(), TopologyError> {
+        if let Some((from, to)) = text.split_once("->") {
+            let from = self.nodes(line, &from.split(',').collect::<Vec<_>>())?;
+            let to = self.nodes(line, &to.split(',').collect::<Vec<_>>())?;
+            for &f in &from {
+                self.edges.extend(to.iter().map(|&t| (f, t)));
+            }
+            return Ok(());
+        }
+        let (keyword, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        match keyword {
+            "node" => {
+                let (name, input) = args
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((args.trim(), ""));
+                let id = self.node(line, name)?;
+                let node = &mut self.nodes[id];
+                if node.declared {
+                    return err(line, format!("duplicate node `{}`", name));
+                }
+                node.declared = true;
+                node.line = line;
+                if !input.trim().is_empty() {
+                    node.input = input
+                        .split(',')
+                        .map(|v| parse_value(line, v))
+                        .collect::<Result<_, _>>()?;
+                }
+            }
+            "chain" | "ring" => {
+                let ids = self.nodes(line, &args.split_whitespace().collect::<Vec<_>>())?;
+                self.edges.extend(ids.windows(2).map(|w| (w[0], w[1])));
+                if keyword == "ring" {
+                    self.edges.push((ids[ids.len() - 1], ids[0]));
+                }
+            }
+            _ => return err(line, format!("unknown statement `{}`", keyword)),
+        }
+        Ok(())
+    }
+    pub fn id(&self, name: &str) -> Option<MachineId> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+    /// Number of `$n` parameters the description refers to.
+    pub fn params(&self) -> usize {
+        self.nodes
+            .iter()
+            .flat_map(|n| &n.input)
+            .filter_map(|v| match v {
+                Value::Param(n) => Some(n + 1),
+                Value::Int(_) => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+    /// Creates a scheduler with one copy of `ic` per node, in order of first mention, and
+    /// queues the initial inputs.
+    pub fn build(&self, ic: &Intcode, params: &[isize]) -> Result<Scheduler, TopologyError> {
+        let mut sched = Scheduler::new();
+        for node in &self.nodes {
+            let id = sched.add(ic.clone());
+            for &v in &node.input {
+                sched.send(
+                    id,
+                    match v {
+                        Value::Int(v) => v,
+                        Value::Param(n) => match params.get(n) {
+                            Some(&v) => v,
+                            None => return err(node.line, format!("missing parameter ${}", n)),
+                        },
+                    },
+                );
+            }
+        }
+        for &(from, to) in &self.edges {
+            sched.connect(from, to);
+        }
+        Ok(sched)
+    }
+    /// Builds the topology and runs it until every machine halted or they deadlock.
+    pub fn run(&self, ic: &Intcode, params: &[isize]) -> Result<Report, Box<dyn Error>> {
+        let mut sched = self.build(ic, params)?;
+        let taps: Vec<Receiver<isize>> = (0..sched.len()).map(|id| sched.tap(id)).collect();
+        let outcome = sched.run()?;
+        let halt_codes = sched.halt_codes();
+        Ok(Report {
+            outcome,
+            outputs: self
+                .nodes
+                .iter()
+                .zip(taps)
+                .map(|(n, tap)| (n.name.clone(), tap.try_iter().collect()))
+                .collect(),
+            halt_codes: self
+                .nodes
+                .iter()
+                .zip(halt_codes)
+                .map(|(n, h)| (n.name.clone(), h))
+                .collect(),
+        })
+    }
+}
+
+impl FromStr for Topology {
+    type Err = TopologyError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let mut topology = Topology::default();
+        for (idx, text) in src.lines().enumerate() {
+            let text = text.split(';').next().unwrap().trim();
+            if !text.is_empty() {
+                topology.parse_line(idx + 1, text)?;
+            }
+        }
+        Ok(topology)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const AMP: &str =
+        "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+
+    #[test]
+    fn ring() -> Result<(), Box<dyn Error>> {
+        let topology: Topology = "
+            ; amplifier feedback loop
+            node a $0, 0
+            node b $1
+            node c $2
+            node d $3
+            node e $4
+            ring a b c d e
+        "
+        .parse()?;
+        assert_eq!(5, topology.params());
+        let report = topology.run(&Intcode::from_str(AMP)?, &[9, 8, 7, 6, 5])?;
+        assert_eq!(Outcome::Halted, report.outcome);
+        assert_eq!(Some(&139_629_729), report.output("e").last());
+        assert_eq!(Some(3), report.halt_code("a"));
+        Ok(())
+    }
+    #[test]
+    fn fan_out() -> Result<(), Box<dyn Error>> {
+        let double = Intcode::from_str("3,9,1002,9,2,9,4,9,99,0")?;
+        let topology: Topology = "
+            node src 1
+            src -> l, r
+            l, r -> sum
+        "
+        .parse()?;
+        assert_eq!(Some(3), topology.id("sum"));
+        let report = topology.run(&double, &[])?;
+        assert_eq!(&[2], report.output("src"));
+        assert_eq!(&[4], report.output("l"));
+        assert_eq!(&[4], report.output("r"));
+        assert_eq!(&[8], report.output("sum"));
+        assert!(report.output("nope").is_empty());
+        Ok(())
+    }
+    #[test]
+    fn errors() -> Result<(), Box<dyn Error>> {
+        let parse = |s: &str| s.parse::<Topology>().unwrap_err().to_string();
+        assert_eq!("line 1: unknown statement `loop`", parse("loop a b"));
+        assert_eq!("line 2: duplicate node `a`", parse("node a\nnode a 1"));
+        assert_eq!("line 1: invalid node name `1a`", parse("a -> 1a"));
+        assert_eq!("line 1: invalid value `x`", parse("node a 1, x"));
+        assert_eq!("line 1: expected at least one node", parse("ring"));
+
+        let topology: Topology = "node a $1".parse()?;
+        assert_eq!(
+            "line 1: missing parameter $1",
+            topology
+                .run(&Intcode::from_str("99")?, &[0])
+                .unwrap_err()
+                .to_string()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,383 @@
+use super::codec::{read_header, read_usize, read_var, write_header, write_uvar, write_var};
+use super::memory::{read_memory, write_memory};
+use super::wide::Cell;
+use super::{Instr, InstrState, Intcode, Memory, Opcode};
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"ICTR";
+const VERSION: u8 = 2;
+
+const WRITE: u8 = 1;
+const INPUT: u8 = 2;
+const OUTPUT: u8 = 4;
+
+/// One executed instruction. `operands` holds the values read by the instruction in
+/// parameter order, after resolving their modes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceEntry<C = isize> {
+    pub pc: isize,
+    pub instr: isize,
+    pub operands: Vec<C>,
+    pub write: Option<(usize, C)>,
+    pub input: Option<C>,
+    pub output: Option<C>,
+}
+
+impl<C: Cell> TraceEntry<C> {
+    fn encode(&self, w: &mut Vec<u8>) -> io::Result<()> {
+        let flags = (self.operands.len() as u8) << 3
+            | self.write.as_ref().map_or(0, |_| WRITE)
+            | self.input.as_ref().map_or(0, |_| INPUT)
+            | self.output.as_ref().map_or(0, |_| OUTPUT);
+        write_var(w, self.pc)?;
+        write_var(w, self.instr)?;
+        w.push(flags);
+        for o in &self.operands {
+            o.write_var(w)?;
+        }
+        if let Some((addr, v)) = &self.write {
+            write_uvar(w, *addr as u64)?;
+            v.write_var(w)?;
+        }
+        for v in self.input.iter().chain(&self.output) {
+            v.write_var(w)?;
+        }
+        Ok(())
+    }
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let pc = read_var(r)?;
+        let instr = read_var(r)?;
+        let mut flags = [0];
+        r.read_exact(&mut flags)?;
+        let flags = flags[0];
+        let operands = (0..flags >> 3)
+            .map(|_| C::read_var(r))
+            .collect::<io::Result<_>>()?;
+        let write = if flags & WRITE != 0 {
+            Some((read_usize(r)?, C::read_var(r)?))
+        } else {
+            None
+        };
+        let input = if flags & INPUT != 0 {
+            Some(C::read_var(r)?)
+        } else {
+            None
+        };
+        let output = if flags & OUTPUT != 0 {
+            Some(C::read_var(r)?)
+        } else {
+            None
+        };
+        Ok(TraceEntry {
+            pc,
+            instr,
+            operands,
+            write,
+            input,
+            output,
+        })
+    }
+    fn operand(&self, i: usize) -> C {
+        self.operands.get(i).cloned().unwrap_or_default()
+    }
+    // Recorded jump targets and base offsets fit, the instruction would have failed
+    // otherwise.
+    fn small(&self, i: usize) -> isize {
+        self.operand(i).to_isize().unwrap_or_default()
+    }
+    /// Applies the effects of this instruction to `ic`.
+    fn apply(&self, ic: &mut Intcode<C>) {
+        if let Some((addr, v)) = &self.write {
+            *ic.mem(*addr) = v.clone();
+        }
+        let op = Instr::decode(self.pc, self.instr).map(|i| i.op);
+        let zero = self.operand(0) == C::default();
+        ic.pc = match op {
+            Ok(Opcode::Jnz) if !zero => self.small(1),
+            Ok(Opcode::Jz) if zero => self.small(1),
+            Ok(Opcode::Halt) | Err(_) => self.pc,
+            Ok(op) => self.pc + op.params() as isize + 1,
+        };
+        if let Ok(Opcode::Arb) = op {
+            ic.base += self.small(0);
+        }
+    }
+}
+
+/// Compact binary log of executed instructions together with the machine state at the
+/// start of the recording.
+#[derive(Clone)]
+pub struct Trace<C = isize> {
+    mem: Box<dyn Memory<C>>,
+    pc: isize,
+    base: isize,
+    data: Vec<u8>,
+    len: usize,
+}
+
+impl<C: Cell> Trace<C> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn entries(&self) -> impl Iterator<Item = TraceEntry<C>> + '_ {
+        let mut data = &self.data[..];
+        (0..self.len).map(move |_| TraceEntry::decode(&mut data).expect("corrupt trace"))
+    }
+    /// Replays the trace from the start of the recording.
+    pub fn replay(&self) -> Replay<'_, C> {
+        let mut ic = Intcode::from_box(self.mem.clone());
+        ic.pc = self.pc;
+        ic.base = self.base;
+        Replay {
+            ic,
+            entries: Box::new(self.entries()),
+            step: 0,
+        }
+    }
+    /// Machine state after the first `step` instructions of the trace.
+    pub fn state_at(&self, step: usize) -> Option<Intcode<C>> {
+        if step > self.len {
+            return None;
+        }
+        let mut replay = self.replay();
+        for _ in 0..step {
+            replay.forward();
+        }
+        Some(replay.ic)
+    }
+    /// Index of the first instruction at which the two traces differ, `None` if they
+    /// are identical.
+    pub fn diverge(&self, other: &Trace<C>) -> Option<usize> {
+        self.entries()
+            .zip(other.entries())
+            .position(|(a, b)| a != b)
+            .or_else(|| {
+                if self.len == other.len {
+                    None
+                } else {
+                    Some(self.len.min(other.len))
+                }
+            })
+    }
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut buf = Vec::new();
+        write_header(&mut buf, MAGIC, VERSION)?;
+        write_var(&mut buf, self.pc)?;
+        write_var(&mut buf, self.base)?;
+        write_memory(&mut buf, &*self.mem)?;
+        write_uvar(&mut buf, self.len as u64)?;
+        w.write_all(&buf)?;
+        w.write_all(&self.data)
+    }
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        read_header(&mut r, MAGIC, VERSION)?;
+        let pc = read_var(&mut r)?;
+        let base = read_var(&mut r)?;
+        let mem = read_memory(&mut r)?;
+        let len = read_usize(&mut r)?;
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)?;
+        let mut check = &data[..];
+        for _ in 0..len {
+            TraceEntry::<C>::decode(&mut check)?;
+        }
+        if !check.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "trailing data"));
+        }
+        Ok(Trace {
+            mem,
+            pc,
+            base,
+            data,
+            len,
+        })
+    }
+}
+
+/// Reconstructs the machine state one recorded instruction at a time.
+pub struct Replay<'a, C: Cell = isize> {
+    ic: Intcode<C>,
+    entries: Box<dyn Iterator<Item = TraceEntry<C>> + 'a>,
+    step: usize,
+}
+
+impl<'a, C: Cell> Replay<'a, C> {
+    pub fn state(&self) -> &Intcode<C> {
+        &self.ic
+    }
+    /// Number of instructions replayed so far.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+    /// Applies the next recorded instruction and returns it.
+    pub fn forward(&mut self) -> Option<TraceEntry<C>> {
+        let entry = self.entries.next()?;
+        entry.apply(&mut self.ic);
+        self.step += 1;
+        Some(entry)
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Tracer<C> {
+    trace: Trace<C>,
+    cur: TraceEntry<C>,
+}
+
+impl<C: Cell> Tracer<C> {
+    pub(super) fn begin(&mut self, pc: isize, instr: isize) {
+        self.cur.pc = pc;
+        self.cur.instr = instr;
+        self.cur.operands.clear();
+        self.cur.write = None;
+        self.cur.input = None;
+        self.cur.output = None;
+    }
+    pub(super) fn read(&mut self, v: C) {
+        self.cur.operands.push(v);
+    }
+    pub(super) fn write(&mut self, addr: usize, v: C) {
+        self.cur.write = Some((addr, v));
+    }
+    pub(super) fn end(&mut self, state: &InstrState<C>) {
+        match state {
+            InstrState::Wait => return,
+            InstrState::Output(o) => self.cur.output = Some(o.clone()),
+            _ => (),
+        }
+        if self.cur.instr % 100 == Opcode::In.code() {
+            self.cur.input = self.cur.write.as_ref().map(|(_, v)| v.clone());
+        }
+        self.cur
+            .encode(&mut self.trace.data)
+            .expect("writing to a Vec never fails");
+        self.trace.len += 1;
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Starts recording every executed instruction, discarding any previous trace.
+    pub fn start_trace(&mut self) {
+        self.tracer = Some(Box::new(Tracer {
+            trace: Trace {
+                mem: self.mem.clone(),
+                pc: self.pc,
+                base: self.base,
+                data: Vec::new(),
+                len: 0,
+            },
+            cur: TraceEntry::default(),
+        }));
+    }
+    pub fn stop_trace(&mut self) -> Option<Trace<C>> {
+        self.tracer.take().map(|t| t.trace)
+    }
+    pub fn trace(&self) -> Option<&Trace<C>> {
+        self.tracer.as_ref().map(|t| &t.trace)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::ADDRESS_LIMIT;
+    use std::error::Error;
+    use std::str::FromStr;
+
+    const AMP: &str = "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0";
+
+    fn traced(input: &[isize]) -> Result<Trace, Box<dyn Error>> {
+        let mut ic = Intcode::from_str(AMP)?;
+        ic.start_trace();
+        ic.run_input(input)?;
+        Ok(ic.stop_trace().unwrap())
+    }
+
+    #[test]
+    fn entries() -> Result<(), Box<dyn Error>> {
+        let trace = traced(&[1, 0])?;
+        assert_eq!(8, trace.len());
+        let entries: Vec<_> = trace.entries().collect();
+        assert_eq!(
+            TraceEntry {
+                pc: 0,
+                instr: 3,
+                operands: vec![],
+                write: Some((23, 1)),
+                input: Some(1),
+                output: None,
+            },
+            entries[0]
+        );
+        assert_eq!(
+            TraceEntry {
+                pc: 4,
+                instr: 1002,
+                operands: vec![0, 10],
+                write: Some((24, 0)),
+                input: None,
+                output: None,
+            },
+            entries[2]
+        );
+        assert_eq!(Some(4), entries[6].output);
+        assert_eq!(Opcode::Halt.code(), entries[7].instr);
+        Ok(())
+    }
+    #[test]
+    fn replay() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str("109,19,204,-10,1105,1,7,21101,3,4,-5,99")?;
+        let mut states = vec![ic.clone()];
+        ic.start_trace();
+        while !ic.run_input(&[])?.is_halt() {}
+        let trace = ic.stop_trace().unwrap();
+        let mut ic = states[0].clone();
+        for _ in 0..trace.len() {
+            ic.step(&mut std::iter::empty())?;
+            states.push(ic.clone());
+        }
+        for (i, state) in states.iter().enumerate() {
+            let replayed = trace.state_at(i).unwrap();
+            assert_eq!(state.pc(), replayed.pc());
+            assert_eq!(state.base(), replayed.base());
+            assert_eq!(state.memory(), replayed.memory());
+        }
+        assert!(trace.state_at(trace.len() + 1).is_none());
+        Ok(())
+    }
+    #[test]
+    fn diverge() -> Result<(), Box<dyn Error>> {
+        let a = traced(&[1, 0])?;
+        assert_eq!(None, a.diverge(&traced(&[1, 0])?));
+        assert_eq!(Some(1), a.diverge(&traced(&[1, 5])?));
+        assert_eq!(Some(0), a.diverge(&traced(&[2, 0])?));
+        assert_eq!(Some(1), a.diverge(&traced(&[1])?));
+        Ok(())
+    }
+    #[test]
+    fn file() -> Result<(), Box<dyn Error>> {
+        let trace = traced(&[4, 3])?;
+        let mut buf = Vec::new();
+        trace.write_to(&mut buf)?;
+        let loaded = Trace::read_from(&buf[..])?;
+        assert_eq!(None, trace.diverge(&loaded));
+        assert_eq!(
+            trace.state_at(5).unwrap().memory(),
+            loaded.state_at(5).unwrap().memory()
+        );
+        assert!(Trace::<isize>::read_from(&buf[..buf.len() - 1]).is_err());
+        // Move the only memory run past the address limit.
+        assert_eq!(&[0, 1, 0], &buf[7..10]);
+        let mut bad = buf[..9].to_vec();
+        write_uvar(&mut bad, ADDRESS_LIMIT as u64)?;
+        bad.extend(&buf[10..]);
+        let err = Trace::<isize>::read_from(&bad[..]).err();
+        assert_eq!(Some(io::ErrorKind::InvalidData), err.map(|e| e.kind()));
+        buf[4] = 1;
+        assert!(Trace::<isize>::read_from(&buf[..]).is_err());
+        Ok(())
+    }
+}
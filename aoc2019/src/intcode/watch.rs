@@ -0,0 +1,169 @@
+use super::wide::Cell;
+use super::Intcode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit<C = isize> {
+    pub pc: isize,
+    pub addr: usize,
+    pub access: Access,
+    pub old: C,
+    pub new: C,
+}
+
+type Callback<C> = Arc<Mutex<dyn FnMut(&WatchHit<C>) + Send>>;
+
+#[derive(Clone)]
+pub enum WatchAction<C = isize> {
+    /// Finish the current instruction, then stop with `State::Watch`.
+    Stop,
+    /// Invoke the callback and keep running. Clones of the machine share the callback.
+    Call(Callback<C>),
+}
+
+impl<C> WatchAction<C> {
+    pub fn call<F: FnMut(&WatchHit<C>) + Send + 'static>(f: F) -> Self {
+        WatchAction::Call(Arc::new(Mutex::new(f)))
+    }
+}
+
+#[derive(Clone, Default)]
+pub(super) struct Watchpoints<C> {
+    watches: HashMap<(usize, Access), WatchAction<C>>,
+    pub(super) stop: Option<WatchHit<C>>,
+}
+
+impl<C> Watchpoints<C> {
+    pub(super) fn is_empty(&self) -> bool {
+        self.watches.is_empty()
+    }
+    /// Drops a hit nobody took, so it does not end the next run.
+    pub(super) fn clear_stop(&mut self) {
+        self.stop = None;
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Watches reads or writes of `addr` through instruction parameters.
+    pub fn watch(&mut self, addr: usize, access: Access, action: WatchAction<C>) {
+        self.watch.watches.insert((addr, access), action);
+    }
+    pub fn unwatch(&mut self, addr: usize, access: Access) -> bool {
+        self.watch.watches.remove(&(addr, access)).is_some()
+    }
+    /// Takes the hit of a `WatchAction::Stop` watchpoint that ended the last instruction.
+    pub fn take_watch_stop(&mut self) -> Option<WatchHit<C>> {
+        self.watch.stop.take()
+    }
+    pub(super) fn check_watch(&mut self, addr: usize, access: Access, old: &C, new: &C) {
+        let action = match self.watch.watches.get(&(addr, access)) {
+            Some(action) => action,
+            None => return,
+        };
+        let hit = WatchHit {
+            pc: self.pc,
+            addr,
+            access,
+            old: old.clone(),
+            new: new.clone(),
+        };
+        match action {
+            WatchAction::Stop => {
+                self.watch.stop.get_or_insert(hit);
+            }
+            WatchAction::Call(f) => {
+                let mut f = f.lock().unwrap_or_else(|e| e.into_inner());
+                (*f)(&hit)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{IntcodeError, State};
+    use std::error::Error;
+    use std::str::FromStr;
+
+    #[test]
+    fn callback() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str("1,9,10,3,2,3,11,0,99,30,40,50")?;
+        let hits = Arc::new(Mutex::new(Vec::new()));
+        let log = hits.clone();
+        ic.watch(
+            0,
+            Access::Write,
+            WatchAction::call(move |hit| log.lock().unwrap().push(*hit)),
+        );
+        assert_eq!(3500, ic.run()?);
+        assert_eq!(
+            vec![WatchHit {
+                pc: 4,
+                addr: 0,
+                access: Access::Write,
+                old: 1,
+                new: 3500
+            }],
+            *hits.lock().unwrap()
+        );
+        Ok(())
+    }
+    #[test]
+    fn stop() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str("3,9,4,9,1001,9,1,9,99,0")?;
+        ic.watch(9, Access::Read, WatchAction::Stop);
+        let hit = WatchHit {
+            pc: 2,
+            addr: 9,
+            access: Access::Read,
+            old: 7,
+            new: 7,
+        };
+        assert_eq!(State::Watch(vec![7], hit), ic.run_input(&[7])?);
+        assert!(ic.unwatch(9, Access::Read));
+        ic.watch(9, Access::Write, WatchAction::Stop);
+        let hit = WatchHit {
+            pc: 4,
+            addr: 9,
+            access: Access::Write,
+            old: 7,
+            new: 8,
+        };
+        assert_eq!(State::Watch(vec![], hit), ic.run_input(&[])?);
+        assert_eq!(8, ic.pc());
+        assert_eq!(State::Halt(vec![], 3), ic.run_input(&[])?);
+        Ok(())
+    }
+    #[test]
+    fn run_stops() -> Result<(), Box<dyn Error>> {
+        let mut ic = Intcode::from_str("1,9,9,9,1,9,9,9,99,1")?;
+        ic.watch(9, Access::Write, WatchAction::Stop);
+        assert_eq!(Err(IntcodeError::Watchpoint { pc: 0, addr: 9 }), ic.run());
+        assert_eq!(Some(2), ic.take_watch_stop().map(|hit| hit.new));
+        assert!(ic.unwatch(9, Access::Write));
+        assert_eq!(1, ic.run()?);
+
+        // A hit left behind by `step` must not end the next run early.
+        let mut ic = Intcode::from_str("3,9,4,9,1001,9,1,9,99,0")?;
+        ic.watch(9, Access::Read, WatchAction::Stop);
+        ic.step(&mut std::iter::once(7))?;
+        ic.step(&mut std::iter::empty())?;
+        let hit = WatchHit {
+            pc: 4,
+            addr: 9,
+            access: Access::Read,
+            old: 7,
+            new: 7,
+        };
+        assert_eq!(State::Watch(vec![], hit), ic.run_input(&[])?);
+        Ok(())
+    }
+}
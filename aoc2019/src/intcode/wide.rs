@@ -0,0 +1,258 @@
+use super::codec::{read_var, read_var128, write_var, write_var128};
+use super::memory::map_memory;
+use super::Intcode;
+use num_bigint::{BigInt, BigUint};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+/// Value of a memory cell. `Intcode` uses `isize` unless told otherwise, wider cells are
+/// for programs whose values do not fit into one.
+pub trait Cell:
+    Clone + Ord + Default + fmt::Debug + fmt::Display + FromStr + Send + Sync + 'static
+{
+    fn from_isize(v: isize) -> Self;
+    /// The value as an address or opcode, if it fits.
+    fn to_isize(&self) -> Option<isize>;
+    /// `None` on overflow, unless `wrap` is set.
+    fn plus(&self, rhs: &Self, wrap: bool) -> Option<Self>;
+    fn times(&self, rhs: &Self, wrap: bool) -> Option<Self>;
+    /// Zigzag LEB128, the same bytes for a value whatever the cell type.
+    fn write_var<W: Write>(&self, w: &mut W) -> io::Result<()>;
+    fn read_var<R: Read>(r: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! fixed_cell {
+    ($($t:ty),*) => {$(
+        impl Cell for $t {
+            fn from_isize(v: isize) -> Self {
+                v as $t
+            }
+            fn to_isize(&self) -> Option<isize> {
+                isize::try_from(*self).ok()
+            }
+            fn plus(&self, rhs: &Self, wrap: bool) -> Option<Self> {
+                if wrap {
+                    Some(self.wrapping_add(*rhs))
+                } else {
+                    self.checked_add(*rhs)
+                }
+            }
+            fn times(&self, rhs: &Self, wrap: bool) -> Option<Self> {
+                if wrap {
+                    Some(self.wrapping_mul(*rhs))
+                } else {
+                    self.checked_mul(*rhs)
+                }
+            }
+            fn write_var<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                write_var128(w, *self as i128)
+            }
+            fn read_var<R: Read>(r: &mut R) -> io::Result<Self> {
+                <$t>::try_from(read_var128(r)?).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "value too large")
+                })
+            }
+        }
+    )*};
+}
+
+fixed_cell!(i64, i128);
+
+// The default cell keeps the 64 bit codec, it is on the hot path of traces.
+impl Cell for isize {
+    fn from_isize(v: isize) -> Self {
+        v
+    }
+    fn to_isize(&self) -> Option<isize> {
+        Some(*self)
+    }
+    fn plus(&self, rhs: &Self, wrap: bool) -> Option<Self> {
+        if wrap {
+            Some(self.wrapping_add(*rhs))
+        } else {
+            self.checked_add(*rhs)
+        }
+    }
+    fn times(&self, rhs: &Self, wrap: bool) -> Option<Self> {
+        if wrap {
+            Some(self.wrapping_mul(*rhs))
+        } else {
+            self.checked_mul(*rhs)
+        }
+    }
+    fn write_var<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write_var(w, *self)
+    }
+    fn read_var<R: Read>(r: &mut R) -> io::Result<Self> {
+        read_var(r)
+    }
+}
+
+impl Cell for BigInt {
+    fn from_isize(v: isize) -> Self {
+        BigInt::from(v)
+    }
+    fn to_isize(&self) -> Option<isize> {
+        isize::try_from(self).ok()
+    }
+    fn plus(&self, rhs: &Self, _: bool) -> Option<Self> {
+        Some(self + rhs)
+    }
+    fn times(&self, rhs: &Self, _: bool) -> Option<Self> {
+        Some(self * rhs)
+    }
+    fn write_var<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let zigzag: BigInt = if *self < BigInt::default() {
+            -self * 2 - 1
+        } else {
+            self * 2
+        };
+        let mut digits = zigzag.magnitude().to_radix_le(128);
+        let last = digits.len() - 1;
+        for d in &mut digits[..last] {
+            *d |= 0x80;
+        }
+        w.write_all(&digits)
+    }
+    fn read_var<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut digits = Vec::new();
+        loop {
+            let mut byte = [0];
+            r.read_exact(&mut byte)?;
+            digits.push(byte[0] & 0x7f);
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        let zigzag = BigInt::from(BigUint::from_radix_le(&digits, 128).unwrap_or_default());
+        Ok(if zigzag.bit(0) {
+            -(zigzag + 1u8) / 2u8
+        } else {
+            zigzag / 2u8
+        })
+    }
+}
+
+impl<C: Cell> Intcode<C> {
+    /// Parses comma separated cells, `from_str` for any cell type.
+    pub fn parse(src: &str) -> Result<Self, Box<dyn Error>>
+    where
+        <C as FromStr>::Err: Error + 'static,
+    {
+        let mut mem: Vec<C> = src
+            .split(',')
+            .map(|n| n.trim().parse())
+            .collect::<Result<_, _>>()?;
+        mem.reserve(mem.len() * 2);
+        Ok(Intcode::from(mem))
+    }
+}
+
+impl Intcode {
+    /// Copy of the machine with wider cells. Watchpoints, traces and profiles are not
+    /// copied.
+    pub fn to_wide<C: Cell>(&self) -> Intcode<C> {
+        let mut wide = Intcode::from_box(map_memory(&*self.mem, |&v| C::from_isize(v)));
+        wide.pc = self.pc;
+        wide.base = self.base;
+        wide.limit = self.limit;
+        wide.wrap = self.wrap;
+        wide.budget = self.budget.clone();
+        wide
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::intcode::{IntcodeError, State};
+
+    // Squares its input three times.
+    const SQUARE: &str = "
+            in -> [x]
+            mul [x], [x] -> [x]
+            mul [x], [x] -> [x]
+            mul [x], [x] -> [x]
+            out [x]
+            halt
+        x:  data 0
+    ";
+
+    #[test]
+    fn day09() -> Result<(), Box<dyn Error>> {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let mut ic = Intcode::<i128>::parse(quine)?;
+        let code: Vec<i128> = quine
+            .split(',')
+            .map(|v| v.parse())
+            .collect::<Result<_, _>>()?;
+        assert_eq!(&code, ic.run_input(&[])?.out());
+        let mut ic = Intcode::<i64>::parse("1102,34915192,34915192,7,4,7,99,0")?;
+        assert_eq!(
+            State::Halt(vec![1_219_070_632_396_864], 1102),
+            ic.run_input(&[])?
+        );
+        let mut ic = Intcode::<BigInt>::parse("104,1125899906842624,99")?;
+        assert_eq!(
+            vec![BigInt::from(1_125_899_906_842_624i64)],
+            *ic.run_input(&[])?.out()
+        );
+        Ok(())
+    }
+    #[test]
+    fn overflow() -> Result<(), Box<dyn Error>> {
+        let ic = Intcode::from_asm(SQUARE)?;
+        let overflow = IntcodeError::Overflow { pc: 6 };
+        assert_eq!(Err(overflow), ic.clone().run_input(&[1 << 20]));
+        assert_eq!(Err(overflow), ic.to_wide::<i64>().run_input(&[1 << 20]));
+        assert_eq!(
+            Err(IntcodeError::Overflow { pc: 10 }),
+            ic.to_wide::<i128>().run_input(&[1 << 20])
+        );
+
+        let mut wrap = ic.clone();
+        wrap.set_wrap(true);
+        assert_eq!(vec![0], *wrap.to_wide::<i64>().run_input(&[1 << 20])?.out());
+        assert_eq!(vec![0], *wrap.run_input(&[1 << 20])?.out());
+        assert_eq!(
+            vec![BigInt::from(2).pow(160)],
+            *ic.to_wide::<BigInt>()
+                .run_input(&[BigInt::from(1 << 20)])?
+                .out()
+        );
+
+        let mut huge = ic.to_wide::<i128>();
+        *huge.mem(1) = 1 << 70;
+        assert_eq!(Err(IntcodeError::Overflow { pc: 0 }), huge.run_input(&[1]));
+        Ok(())
+    }
+    #[test]
+    fn codec() -> io::Result<()> {
+        let big = BigInt::from(3).pow(100);
+        let values = [
+            BigInt::default(),
+            BigInt::from(-1),
+            BigInt::from(isize::MIN),
+            -big.clone(),
+            big,
+        ];
+        let mut buf = Vec::new();
+        for v in &values {
+            v.write_var(&mut buf)?;
+        }
+        let mut r = &buf[..];
+        for v in &values {
+            assert_eq!(*v, BigInt::read_var(&mut r)?);
+        }
+        assert!(r.is_empty());
+
+        let mut narrow = Vec::new();
+        isize::MIN.write_var(&mut narrow)?;
+        assert_eq!(&buf[2..2 + narrow.len()], &narrow[..]);
+        assert!(i64::read_var(&mut &buf[2 + narrow.len()..]).is_err());
+        Ok(())
+    }
+}